@@ -0,0 +1,20 @@
+use std::process::Command;
+
+// Embeds the current git commit so /v1/version can report which build is running
+fn main() {
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+
+    let sha = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|sha| sha.trim().to_string())
+        .filter(|sha| !sha.is_empty());
+
+    if let Some(sha) = sha {
+        println!("cargo:rustc-env=NEBULET_GIT_SHA={}", sha);
+    }
+}
@@ -0,0 +1,96 @@
+use utoipa::OpenApi;
+
+use crate::api::error::ErrorResponse;
+use crate::api::handlers;
+use crate::models::v1::audit_log::{ContainerEventResponse, ContainerEventsResponse};
+use crate::models::v1::container::{
+    AggregateLogLine, AggregateLogsResponse, BatchCreateResponse, BatchCreateResult, ClusterStats,
+    ContainerAction, ContainerActionRequest, ContainerListResponse, ContainerLogsResponse,
+    ContainerResponse, ContainerStats, ContainerStatus, ContainerStatusUpdate, ContainerTop,
+    CreateContainerRequest, ExecRequest, ExecResponse, FieldError, HealthcheckSpec, PortMapping,
+    PruneContainersResponse, UpdateContainerRequest,
+};
+use crate::models::v1::image::{
+    ImageListResponse, ImageResponse, PullImageRequest, PullImageResponse,
+};
+use crate::models::v1::network::{CreateNetworkRequest, NetworkListResponse, NetworkResponse};
+use crate::models::v1::processor::{ProcessorListResponse, ProcessorResponse};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Nebulet API"),
+    paths(
+        handlers::health_check,
+        handlers::readiness_check,
+        handlers::version,
+        handlers::metrics,
+        handlers::list_containers,
+        handlers::create_container,
+        handlers::create_containers_batch,
+        handlers::get_container,
+        handlers::update_container,
+        handlers::delete_container,
+        handlers::prune_containers,
+        handlers::container_action,
+        handlers::reconcile_container,
+        handlers::get_container_logs,
+        handlers::get_container_stats,
+        handlers::get_container_top,
+        handlers::exec_container,
+        handlers::inspect_container,
+        handlers::get_container_events,
+        handlers::watch_container,
+        handlers::cluster_stats,
+        handlers::get_aggregate_logs,
+        handlers::list_images,
+        handlers::pull_image,
+        handlers::list_networks,
+        handlers::create_network,
+        handlers::delete_network,
+        handlers::list_processors,
+    ),
+    components(schemas(
+        CreateContainerRequest,
+        BatchCreateResult,
+        BatchCreateResponse,
+        UpdateContainerRequest,
+        PortMapping,
+        HealthcheckSpec,
+        ContainerAction,
+        ContainerActionRequest,
+        ContainerResponse,
+        ContainerStatus,
+        ContainerStatusUpdate,
+        ContainerListResponse,
+        ContainerLogsResponse,
+        AggregateLogLine,
+        AggregateLogsResponse,
+        ContainerStats,
+        ContainerTop,
+        ExecRequest,
+        ExecResponse,
+        PruneContainersResponse,
+        ContainerEventResponse,
+        ContainerEventsResponse,
+        ClusterStats,
+        ImageResponse,
+        ImageListResponse,
+        PullImageRequest,
+        PullImageResponse,
+        CreateNetworkRequest,
+        NetworkResponse,
+        NetworkListResponse,
+        ProcessorResponse,
+        ProcessorListResponse,
+        ErrorResponse,
+        FieldError,
+    )),
+    tags(
+        (name = "containers", description = "Container lifecycle"),
+        (name = "images", description = "Images available to the Docker daemon"),
+        (name = "networks", description = "Docker networks managed by the service"),
+        (name = "processors", description = "Processor instances sharing the database"),
+        (name = "health", description = "Health, readiness, version and metrics"),
+    )
+)]
+pub struct ApiDoc;
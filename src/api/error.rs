@@ -0,0 +1,183 @@
+use axum::{
+    extract::rejection::JsonRejection,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use tracing::error;
+use utoipa::ToSchema;
+
+use crate::models::v1::FieldError;
+use crate::services::DockerError;
+
+/// Body returned for every failed request
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub error: String,
+    /// Stable machine-readable code, e.g. "container_not_found"
+    pub code: String,
+    /// Per-field messages, only present for validation failures
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<FieldError>>,
+}
+
+// Error bodies keep the "error" message for existing clients and add a stable "code"
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    #[error("{0}")]
+    InvalidRequest(String),
+    #[error("Invalid JSON body: {1}")]
+    InvalidJson(StatusCode, String),
+    #[error("Request validation failed")]
+    Validation(Vec<FieldError>),
+    #[error("Container not found")]
+    ContainerNotFound,
+    #[error("container name already in use")]
+    NameConflict,
+    #[error("host port {0} already in use by another container")]
+    PortConflict(String),
+    #[error("Network not found")]
+    NetworkNotFound,
+    #[error("network name already in use")]
+    NetworkNameConflict,
+    #[error("{0}")]
+    InvalidState(String),
+    #[error("Container was modified concurrently, retry the request")]
+    ConcurrentModification,
+    #[error("Container limit of {0} reached, remove or stop containers first")]
+    ContainerLimitReached(u64),
+    #[error("Processor is not running, retry later")]
+    ProcessorUnavailable,
+    #[error("Too many requests, retry in {0}s")]
+    RateLimited(u64),
+    #[error("Database error: {0}")]
+    Database(#[from] sea_orm::DbErr),
+    #[error("Docker error: {0:#}")]
+    Docker(anyhow::Error),
+    #[error("Reconciliation failed: {0:#}")]
+    ReconcileFailed(anyhow::Error),
+    #[error("Failed to read archived logs: {0:#}")]
+    LogArchive(anyhow::Error),
+}
+
+impl ApiError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::InvalidJson(status, _) => *status,
+            ApiError::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::ContainerNotFound | ApiError::NetworkNotFound => StatusCode::NOT_FOUND,
+            ApiError::NameConflict
+            | ApiError::PortConflict(_)
+            | ApiError::NetworkNameConflict
+            | ApiError::InvalidState(_)
+            | ApiError::ConcurrentModification
+            | ApiError::ContainerLimitReached(_) => StatusCode::CONFLICT,
+            ApiError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::ProcessorUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Docker(e) => match e.downcast_ref::<DockerError>() {
+                Some(DockerError::ContainerNotFound(_) | DockerError::ImageNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                Some(DockerError::RegistryAuth(_)) => StatusCode::BAD_GATEWAY,
+                Some(DockerError::Unavailable(_)) => StatusCode::SERVICE_UNAVAILABLE,
+                Some(DockerError::Timeout(_)) => StatusCode::GATEWAY_TIMEOUT,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+            ApiError::Database(_) | ApiError::ReconcileFailed(_) | ApiError::LogArchive(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::InvalidRequest(_) => "invalid_request",
+            ApiError::InvalidJson(..) => "invalid_json",
+            ApiError::Validation(_) => "validation_failed",
+            ApiError::ContainerNotFound => "container_not_found",
+            ApiError::NameConflict => "container_name_conflict",
+            ApiError::PortConflict(_) => "host_port_conflict",
+            ApiError::NetworkNotFound => "network_not_found",
+            ApiError::NetworkNameConflict => "network_name_conflict",
+            ApiError::InvalidState(_) => "invalid_state",
+            ApiError::ConcurrentModification => "concurrent_modification",
+            ApiError::ContainerLimitReached(_) => "container_limit_reached",
+            ApiError::ProcessorUnavailable => "processor_unavailable",
+            ApiError::RateLimited(_) => "rate_limited",
+            ApiError::Database(_) => "db_error",
+            ApiError::Docker(e) => match e.downcast_ref::<DockerError>() {
+                Some(DockerError::ContainerNotFound(_)) => "docker_container_not_found",
+                Some(DockerError::ImageNotFound(_)) => "image_not_found",
+                Some(DockerError::RegistryAuth(_)) => "registry_auth_failed",
+                Some(DockerError::Unavailable(_)) => "docker_unavailable",
+                Some(DockerError::Timeout(_)) => "docker_timeout",
+                _ => "docker_error",
+            },
+            ApiError::ReconcileFailed(_) => "reconcile_failed",
+            ApiError::LogArchive(_) => "log_archive_error",
+        }
+    }
+
+    // Internal failures are logged in full but only summarised to the client
+    fn message(&self) -> String {
+        match self {
+            ApiError::Database(_) => "Database error".to_string(),
+            ApiError::Docker(e) => match e.downcast_ref::<DockerError>() {
+                Some(DockerError::ContainerNotFound(_)) => {
+                    "Container no longer exists in Docker".to_string()
+                }
+                // Neither carries more than the image reference
+                Some(error @ (DockerError::ImageNotFound(_) | DockerError::RegistryAuth(_))) => {
+                    error.to_string()
+                }
+                Some(DockerError::Unavailable(_)) => "Docker daemon unreachable".to_string(),
+                Some(DockerError::Timeout(_)) => "Docker request timed out".to_string(),
+                _ => "Docker error".to_string(),
+            },
+            ApiError::ReconcileFailed(_) => "Reconciliation failed".to_string(),
+            ApiError::LogArchive(_) => "Failed to read archived logs".to_string(),
+            other => other.to_string(),
+        }
+    }
+}
+
+// Keeps axum's status, e.g. 415 for a missing content type and 422 for mismatched fields
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        ApiError::InvalidJson(rejection.status(), rejection.body_text())
+    }
+}
+
+impl From<ApiError> for ErrorResponse {
+    fn from(error: ApiError) -> Self {
+        ErrorResponse {
+            error: error.message(),
+            code: error.code().to_string(),
+            fields: match error {
+                ApiError::Validation(fields) => Some(fields),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        if status.is_server_error() {
+            error!("Request failed: {}", self);
+        }
+
+        if let ApiError::RateLimited(retry_after_secs) = self {
+            return (
+                status,
+                [(header::RETRY_AFTER, retry_after_secs.to_string())],
+                Json(ErrorResponse::from(self)),
+            )
+                .into_response();
+        }
+        (status, Json(ErrorResponse::from(self))).into_response()
+    }
+}
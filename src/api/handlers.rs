@@ -1,135 +1,1419 @@
-use anyhow::Result;
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
     Json,
 };
-use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, Set};
+use bollard::models::ContainerInspectResponse;
+use chrono::{DateTime, SecondsFormat, Utc};
+use futures::StreamExt;
+use sea_orm::sea_query::{BinOper, Expr, Func, LikeExpr, LockType, Query as SeaQuery};
+use sea_orm::{
+    ActiveValue::{self, NotSet},
+    ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DatabaseTransaction, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set, SqlErr, Statement, TransactionTrait,
+};
 use serde_json::json;
-use tracing::{error, info};
+use std::collections::{BTreeMap, HashSet};
+use std::convert::Infallible;
+use std::time::Duration;
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    mpsc, oneshot, watch,
+};
+use tracing::{error, info, warn};
 
+use crate::api::error::ApiError;
+use crate::api::extract::ApiJson;
+use crate::config::Config;
+use crate::models::v1::audit_log::{
+    self, Column as AuditLogColumn, ContainerEventResponse, ContainerEventsResponse,
+    Entity as AuditLogEntity,
+};
 use crate::models::v1::container::{
-    ContainerResponse, CreateContainerRequest, Entity as ContainerEntity, Model as ContainerModel,
+    host_port_like_pattern, label_like_pattern, prefix_like_pattern,
+    ActiveModel as ContainerActiveModel, AggregateLogLine, AggregateLogsQuery,
+    AggregateLogsResponse, BatchCreateResponse, BatchCreateResult, ClusterStats, ClusterStatsQuery,
+    Column as ContainerColumn, ContainerAction, ContainerActionRequest, ContainerListResponse,
+    ContainerLogsQuery, ContainerLogsResponse, ContainerResponse, ContainerStats, ContainerStatus,
+    ContainerStatusUpdate, ContainerTop, CreateContainerQuery, CreateContainerRequest,
+    Entity as ContainerEntity, ExecRequest, ExecResponse, GetContainerQuery, ListContainersQuery,
+    Model as ContainerModel, PruneContainersQuery, PruneContainersResponse, UpdateContainerRequest,
+};
+use crate::models::v1::idempotency_key;
+use crate::models::v1::image::{
+    ImageListResponse, ListImagesQuery, PullImageRequest, PullImageResponse,
+};
+use crate::models::v1::network::{
+    Column as NetworkColumn, CreateNetworkRequest, Entity as NetworkEntity, Model as NetworkModel,
+    NetworkListResponse, NetworkResponse,
 };
+use crate::models::v1::processor::{
+    Column as HeartbeatColumn, Entity as HeartbeatEntity, ProcessorListResponse, ProcessorResponse,
+};
+use crate::services::{
+    is_container_gone, DockerApi, DockerError, DockerService, LogArchive, Readiness,
+    ReconcileRequest,
+};
+use metrics_exporter_prometheus::PrometheusHandle;
+
+const DEFAULT_LOG_TAIL: usize = 100;
+const MAX_AGGREGATE_LOG_CONTAINERS: usize = 50;
+// Total length of the lines in one aggregate logs response, older lines beyond it are dropped
+const MAX_AGGREGATE_LOG_BYTES: usize = 1024 * 1024;
+const DEFAULT_PAGE_LIMIT: u64 = 50;
+const MAX_PAGE_LIMIT: u64 = 500;
+const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+const MAX_BATCH_SIZE: usize = 100;
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: DatabaseConnection,
+    pub docker: DockerService,
+    pub config: Config,
+    pub metrics: PrometheusHandle,
+    pub readiness: watch::Receiver<Readiness>,
+    /// Status changes published by the processor, subscribed to by WebSocket watchers
+    pub status_updates: broadcast::Sender<ContainerStatusUpdate>,
+    /// Containers to reconcile immediately, handled by the processor
+    pub reconcile_requests: mpsc::Sender<ReconcileRequest>,
+    /// Logs the processor copied to disk, None unless CONTAINER_LOG_DIR is set
+    pub log_archive: Option<LogArchive>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/v1/health",
+    tag = "health",
+    responses((status = 200, description = "Service is up", body = Object))
+)]
 pub async fn health_check() -> (StatusCode, Json<serde_json::Value>) {
     (StatusCode::OK, Json(json!({ "status": "healthy" })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/v1/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "Database and Docker are reachable and the processor is running", body = Object),
+        (status = 503, description = "A dependency check failed or the processor stopped ticking", body = Object)
+    )
+)]
+pub async fn readiness_check(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let backend = state.db.get_database_backend();
+    let database = match state
+        .db
+        .execute(Statement::from_string(backend, "SELECT 1".to_string()))
+        .await
+    {
+        Ok(_) => json!({ "status": "ok" }),
+        Err(e) => {
+            error!("Readiness check failed for database: {}", e);
+            json!({ "status": "error", "error": e.to_string() })
+        }
+    };
+
+    // Docker connectivity is checked by the processor on every tick, calls failing to
+    // reach the daemon in between report it as degraded until a reconnect succeeds
+    let readiness = state.readiness.borrow().clone();
+    let health = state.docker.health();
+    let docker = match (readiness.docker_version, readiness.docker_error) {
+        (Some(version), _) if !health.connected => json!({
+            "status": "degraded",
+            "version": version,
+            "error": health.error,
+            "failed_reconnects": health.failed_reconnects,
+        }),
+        (Some(version), _) => json!({ "status": "ok", "version": version }),
+        (None, error) => json!({
+            "status": "error",
+            "error": error.unwrap_or_else(|| "Docker has not been checked yet".to_string()),
+        }),
+    };
+    // A tick taking several intervals points to a wedged processor, so the instance is
+    // taken out of rotation until a tick finishes again
+    let stale_after = Duration::from_secs(state.config.processor_interval_secs.max(1)) * 3;
+    let processor = match (readiness.initialized, readiness.last_tick) {
+        (false, _) => json!({ "status": "error", "error": "Processor is still initializing" }),
+        (true, Some(last_tick)) if last_tick.elapsed() > stale_after => json!({
+            "status": "stale",
+            "last_tick_secs_ago": last_tick.elapsed().as_secs(),
+            "error": format!("No reconciliation tick finished in the last {}s", stale_after.as_secs()),
+        }),
+        (true, last_tick) => json!({
+            "status": "ok",
+            "last_tick_secs_ago": last_tick.map(|last_tick| last_tick.elapsed().as_secs()),
+        }),
+    };
+
+    let ready =
+        database["status"] == "ok" && docker["status"] != "error" && processor["status"] == "ok";
+    let status_code = match ready {
+        true => StatusCode::OK,
+        false => StatusCode::SERVICE_UNAVAILABLE,
+    };
+
+    (
+        status_code,
+        Json(json!({
+            "status": if ready { "ready" } else { "not_ready" },
+            "checks": {
+                "database": database,
+                "docker": docker,
+                "processor": processor,
+            }
+        })),
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/version",
+    tag = "health",
+    responses((status = 200, description = "Build and Docker versions", body = Object))
+)]
+pub async fn version(State(state): State<AppState>) -> (StatusCode, Json<serde_json::Value>) {
+    // Cached from the last Docker handshake, None until Docker was reachable once
+    let docker_version = state.docker.health().version;
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "git_sha": option_env!("NEBULET_GIT_SHA"),
+            "docker_version": docker_version,
+        })),
+    )
+}
+
+// Removed containers only stay around for their history, so they are a 404 by default
+async fn find_container(
+    db: &DatabaseConnection,
+    container_id: &str,
+) -> Result<ContainerModel, ApiError> {
+    find_container_including_deleted(db, container_id, false).await
+}
+
+async fn find_container_including_deleted(
+    db: &DatabaseConnection,
+    container_id: &str,
+    include_deleted: bool,
+) -> Result<ContainerModel, ApiError> {
+    let mut select = ContainerEntity::find_by_id(container_id.to_string());
+    if !include_deleted {
+        select = select.filter(ContainerColumn::DeletedAt.is_null());
+    }
+    select.one(db).await?.ok_or(ApiError::ContainerNotFound)
+}
+
+// Checks that don't need the database, shared by single and batch creation
+fn check_create_request(config: &Config, request: &CreateContainerRequest) -> Result<(), ApiError> {
+    request.validate_fields().map_err(ApiError::Validation)?;
+    request.validate().map_err(ApiError::InvalidRequest)?;
+
+    if let (Some(memory_mb), Some(max_memory_mb)) = (request.memory_mb, config.max_memory_mb) {
+        if memory_mb > max_memory_mb {
+            return Err(ApiError::InvalidRequest(format!(
+                "Requested memory {}MB exceeds the maximum of {}MB",
+                memory_mb, max_memory_mb
+            )));
+        }
+    }
+    Ok(())
+}
+
+// Docker only reports a missing network once the processor creates the container,
+// so it's checked up front to reject the request instead
+async fn check_network(
+    docker: &DockerService,
+    request: &CreateContainerRequest,
+) -> Result<(), ApiError> {
+    let Some(network) = &request.network else {
+        return Ok(());
+    };
+    if !docker
+        .network_exists(network)
+        .await
+        .map_err(ApiError::Docker)?
+    {
+        return Err(ApiError::InvalidRequest(format!(
+            "Network {} does not exist",
+            network
+        )));
+    }
+    Ok(())
+}
+
+fn idempotency_key_from(headers: &HeaderMap) -> Result<Option<String>, ApiError> {
+    let Some(value) = headers.get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(None);
+    };
+    match value.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_IDEMPOTENCY_KEY_LEN => {
+            Ok(Some(key.to_string()))
+        }
+        _ => Err(ApiError::InvalidRequest(format!(
+            "Idempotency-Key must be 1-{} visible ASCII characters",
+            MAX_IDEMPOTENCY_KEY_LEN
+        ))),
+    }
+}
+
+async fn replayed_response(
+    state: &AppState,
+    key: Option<&str>,
+) -> Result<Option<ContainerResponse>, ApiError> {
+    let Some(key) = key else {
+        return Ok(None);
+    };
+    let ttl_secs = state.config.idempotency_key_ttl_secs;
+    let response = idempotency_key::find_response(&state.db, key, ttl_secs).await?;
+    if response.is_some() {
+        info!("Replaying create for idempotency key {}", key);
+    }
+    Ok(response)
+}
+
+// Only writes if nobody changed the row since it was read, so a processor transition
+// landing in between is never silently overwritten
+async fn update_if_unchanged(
+    txn: &DatabaseTransaction,
+    container: &ContainerModel,
+    mut active_model: ContainerActiveModel,
+) -> Result<ContainerModel, ApiError> {
+    active_model.version = Set(container.version + 1);
+    // Ownership belongs to the processors and isn't covered by the version check
+    active_model.owner = NotSet;
+    // Stopping, restarting or removing a running container freezes its uptime
+    if let ActiveValue::Set(status) = &active_model.status {
+        let was_up = matches!(container.status.as_str(), "Running" | "Paused");
+        if was_up && !matches!(status.as_str(), "Running" | "Paused") {
+            active_model.finished_at = Set(Some(chrono::Utc::now()));
+        }
+    }
+    let result = ContainerEntity::update_many()
+        .set(active_model)
+        .filter(ContainerColumn::Id.eq(container.id.clone()))
+        .filter(ContainerColumn::Version.eq(container.version))
+        .exec(txn)
+        .await?;
+    if result.rows_affected == 0 {
+        return Err(ApiError::ConcurrentModification);
+    }
+
+    ContainerEntity::find_by_id(container.id.clone())
+        .one(txn)
+        .await?
+        .ok_or(ApiError::ContainerNotFound)
+}
+
+// Statuses that hold or will hold Docker resources. They count toward MAX_CONTAINERS and
+// keep their host ports, a Failed container's Docker container is already gone
+const LIMITED_STATUSES: [&str; 6] = [
+    "Pending",
+    "Pulling",
+    "Created",
+    "Running",
+    "Paused",
+    "Restarting",
+];
+
+// Checked before the insert without a lock, concurrent creates can overshoot the limit
+// by the number of requests in flight
+async fn check_container_limit(state: &AppState, adding: u64) -> Result<(), ApiError> {
+    let Some(max_containers) = state.config.max_containers else {
+        return Ok(());
+    };
+    let count = ContainerEntity::find()
+        .filter(ContainerColumn::DeletedAt.is_null())
+        .filter(ContainerColumn::Status.is_in(LIMITED_STATUSES))
+        .count(&state.db)
+        .await?;
+    if count + adding > max_containers {
+        return Err(ApiError::ContainerLimitReached(max_containers));
+    }
+    Ok(())
+}
+
+async fn name_in_use(
+    db: &DatabaseConnection,
+    name: &str,
+    exclude_id: Option<&str>,
+) -> Result<bool, sea_orm::DbErr> {
+    let mut select = ContainerEntity::find()
+        .filter(ContainerColumn::Name.eq(name))
+        .filter(ContainerColumn::DeletedAt.is_null());
+    if let Some(exclude_id) = exclude_id {
+        select = select.filter(ContainerColumn::Id.ne(exclude_id));
+    }
+    Ok(select.one(db).await?.is_some())
+}
+
+// Docker binds a host port for one container only, so a second claim is refused here
+// instead of failing when the processor starts the container. SQL narrows the rows down
+// to those mapping one of the host port numbers, the protocol is compared afterwards.
+async fn check_host_ports(
+    db: &DatabaseConnection,
+    request: &CreateContainerRequest,
+) -> Result<(), ApiError> {
+    let Some(ports) = request.ports.as_ref().filter(|ports| !ports.is_empty()) else {
+        return Ok(());
+    };
+    let mut mapping_a_port = Condition::any();
+    for port in ports {
+        mapping_a_port = mapping_a_port.add(
+            Expr::col(ContainerColumn::Ports)
+                .like(LikeExpr::new(host_port_like_pattern(port.host_port)).escape('\\')),
+        );
+    }
+    let taken: HashSet<(u32, String)> = ContainerEntity::find()
+        .filter(ContainerColumn::DeletedAt.is_null())
+        .filter(ContainerColumn::Status.is_in(LIMITED_STATUSES))
+        .filter(mapping_a_port)
+        .all(db)
+        .await?
+        .iter()
+        .flat_map(ContainerModel::port_mappings)
+        .map(|port| (port.host_port, port.protocol().to_string()))
+        .collect();
+    match ports
+        .iter()
+        .find(|port| taken.contains(&(port.host_port, port.protocol().to_string())))
+    {
+        Some(port) => Err(ApiError::PortConflict(format!(
+            "{}/{}",
+            port.host_port,
+            port.protocol()
+        ))),
+        None => Ok(()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "health",
+    responses((status = 200, description = "Prometheus metrics", body = String, content_type = "text/plain"))
+)]
+pub async fn metrics(State(state): State<AppState>) -> String {
+    state.metrics.render()
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/containers",
+    tag = "containers",
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Repeats with the same key return the original response"),
+        CreateContainerQuery
+    ),
+    request_body = CreateContainerRequest,
+    responses(
+        (status = 200, description = "Dry run passed, nothing was created", body = ContainerResponse),
+        (status = 201, description = "Container recorded as Pending", body = ContainerResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 409, description = "Name or host port already in use, or container limit reached", body = ErrorResponse),
+        (status = 422, description = "Field validation failed", body = ErrorResponse),
+        (status = 429, description = "Create rate limit exceeded, see Retry-After", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse)
+    )
+)]
 pub async fn create_container(
     State(state): State<AppState>,
-    Json(request): Json<CreateContainerRequest>,
-) -> Result<(StatusCode, Json<ContainerResponse>), (StatusCode, Json<serde_json::Value>)> {
+    headers: HeaderMap,
+    Query(query): Query<CreateContainerQuery>,
+    ApiJson(request): ApiJson<CreateContainerRequest>,
+) -> Result<(StatusCode, Json<ContainerResponse>), ApiError> {
     info!("Creating container: {}", request.name);
 
-    let mut container_model: ContainerModel = request.clone().into();
+    check_create_request(&state.config, &request)?;
+
+    // Same checks as a real create, but nothing is written, no key is replayed and Docker
+    // isn't contacted, so the network is left unchecked
+    if query.dry_run.unwrap_or(false) {
+        if name_in_use(&state.db, &request.name, None).await? {
+            return Err(ApiError::NameConflict);
+        }
+        check_host_ports(&state.db, &request).await?;
+        check_container_limit(&state, 1).await?;
+        let container_model: ContainerModel = request.into();
+        info!("Dry run passed for container: {}", container_model.name);
+        return Ok((StatusCode::OK, Json(container_model.into())));
+    }
+
+    check_network(&state.docker, &request).await?;
 
-    // Set initial status to "Pending" - processor will handle Docker creation
-    container_model.status = "Pending".to_string();
-    container_model.docker_id = None;
+    let idempotency_key = idempotency_key_from(&headers)?;
+    if let Some(response) = replayed_response(&state, idempotency_key.as_deref()).await? {
+        return Ok((StatusCode::CREATED, Json(response)));
+    }
 
+    if name_in_use(&state.db, &request.name, None).await? {
+        // The name may have been taken by a concurrent retry carrying the same key
+        if let Some(response) = replayed_response(&state, idempotency_key.as_deref()).await? {
+            return Ok((StatusCode::CREATED, Json(response)));
+        }
+        return Err(ApiError::NameConflict);
+    }
+    check_host_ports(&state.db, &request).await?;
+    check_container_limit(&state, 1).await?;
+
+    // Recorded as Pending - processor will handle Docker creation
+    let container_model: ContainerModel = request.clone().into();
     let container_active_model = container_model.clone().into_active_model();
+    let response: ContainerResponse = container_model.clone().into();
 
+    let txn = state.db.begin().await?;
+    if let Some(key) = &idempotency_key {
+        // A concurrent request with the same key got there first, replay its result instead
+        if let Err(e) = idempotency_key::record(&txn, key, &response).await {
+            txn.rollback().await?;
+            if !matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) {
+                return Err(e.into());
+            }
+            let original = replayed_response(&state, Some(key))
+                .await?
+                .ok_or(ApiError::Database(e))?;
+            return Ok((StatusCode::CREATED, Json(original)));
+        }
+    }
     ContainerEntity::insert(container_active_model)
-        .exec(&state.db)
-        .await
-        .map_err(|e| {
-            error!("Failed to create container in database: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({ "error": "Database error" })),
-            )
-        })?;
+        .exec(&txn)
+        .await?;
+    audit_log::record_transition(&txn, &container_model.id, None, "Pending", None).await?;
+    txn.commit().await?;
 
-    let response: ContainerResponse = container_model.into();
+    crate::metrics::record_container_created();
 
     info!("Container record created successfully: {}", response.id);
     Ok((StatusCode::CREATED, Json(response)))
 }
 
+#[utoipa::path(
+    post,
+    path = "/v1/containers/batch",
+    tag = "containers",
+    request_body = Vec<CreateContainerRequest>,
+    responses(
+        (status = 201, description = "All containers recorded as Pending", body = BatchCreateResponse),
+        (status = 400, description = "Empty or oversized batch", body = ErrorResponse),
+        (status = 409, description = "Batch would exceed the container limit", body = ErrorResponse),
+        (status = 422, description = "Nothing was created, the failing items are listed", body = BatchCreateResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse)
+    )
+)]
+pub async fn create_containers_batch(
+    State(state): State<AppState>,
+    ApiJson(requests): ApiJson<Vec<CreateContainerRequest>>,
+) -> Result<(StatusCode, Json<BatchCreateResponse>), ApiError> {
+    if requests.is_empty() || requests.len() > MAX_BATCH_SIZE {
+        return Err(ApiError::InvalidRequest(format!(
+            "Batch must contain between 1 and {} containers",
+            MAX_BATCH_SIZE
+        )));
+    }
+    info!("Creating batch of {} containers", requests.len());
+    check_container_limit(&state, requests.len() as u64).await?;
+
+    let mut failures = Vec::new();
+    let mut names = HashSet::new();
+    let mut host_ports = HashSet::new();
+    for (index, request) in requests.iter().enumerate() {
+        let checked = match check_create_request(&state.config, request) {
+            Ok(()) if !names.insert(request.name.as_str()) => Err(ApiError::NameConflict),
+            Ok(()) if name_in_use(&state.db, &request.name, None).await? => {
+                Err(ApiError::NameConflict)
+            }
+            checked => checked,
+        };
+        // Earlier items of the batch claim their host ports like existing containers do
+        let claimed = request
+            .ports
+            .iter()
+            .flatten()
+            .map(|port| (port.host_port, port.protocol().to_string()))
+            .filter(|port| !host_ports.insert(port.clone()))
+            .collect::<Vec<_>>();
+        let checked = match (checked, claimed.first()) {
+            (Ok(()), Some((host_port, protocol))) => Err(ApiError::PortConflict(format!(
+                "{}/{}",
+                host_port, protocol
+            ))),
+            (Ok(()), None) => check_host_ports(&state.db, request).await,
+            (checked, _) => checked,
+        };
+        let checked = match checked {
+            Ok(()) => check_network(&state.docker, request).await,
+            checked => checked,
+        };
+        if let Err(e) = checked {
+            failures.push(BatchCreateResult {
+                index,
+                container: None,
+                error: Some(e.into()),
+            });
+        }
+    }
+
+    if !failures.is_empty() {
+        return Ok((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(BatchCreateResponse { items: failures }),
+        ));
+    }
+
+    let models: Vec<ContainerModel> = requests.into_iter().map(ContainerModel::from).collect();
+
+    let txn = state.db.begin().await?;
+    ContainerEntity::insert_many(
+        models
+            .iter()
+            .cloned()
+            .map(ContainerModel::into_active_model),
+    )
+    .exec(&txn)
+    .await?;
+    for model in &models {
+        audit_log::record_transition(&txn, &model.id, None, "Pending", None).await?;
+    }
+    txn.commit().await?;
+
+    let items = models
+        .into_iter()
+        .enumerate()
+        .map(|(index, model)| {
+            crate::metrics::record_container_created();
+            BatchCreateResult {
+                index,
+                container: Some(model.into()),
+                error: None,
+            }
+        })
+        .collect();
+
+    info!("Batch container records created successfully");
+    Ok((StatusCode::CREATED, Json(BatchCreateResponse { items })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/containers",
+    tag = "containers",
+    params(ListContainersQuery),
+    responses(
+        (status = 200, description = "One page of containers", body = ContainerListResponse),
+        (status = 400, description = "Invalid query", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse)
+    )
+)]
 pub async fn list_containers(
     State(state): State<AppState>,
-) -> Result<(StatusCode, Json<Vec<ContainerResponse>>), (StatusCode, Json<serde_json::Value>)> {
-    let containers = ContainerEntity::find().all(&state.db).await.map_err(|e| {
-        error!("Failed to fetch containers: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "error": "Database error" })),
-        )
-    })?;
+    Query(query): Query<ListContainersQuery>,
+) -> Result<(StatusCode, Json<ContainerListResponse>), ApiError> {
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_LIMIT);
+    let offset = query.offset.unwrap_or(0);
+
+    if !(1..=MAX_PAGE_LIMIT).contains(&limit) {
+        return Err(ApiError::InvalidRequest(format!(
+            "limit must be between 1 and {}",
+            MAX_PAGE_LIMIT
+        )));
+    }
+
+    let statuses = query.statuses().map_err(ApiError::InvalidRequest)?;
+    let labels = query.labels().map_err(ApiError::InvalidRequest)?;
+    let sort_column = query.sort_column().map_err(ApiError::InvalidRequest)?;
+    let sort_order = query.sort_order().map_err(ApiError::InvalidRequest)?;
+
+    let mut select = ContainerEntity::find();
+    if !query.include_deleted.unwrap_or(false) {
+        select = select.filter(ContainerColumn::DeletedAt.is_null());
+    }
+    if !statuses.is_empty() {
+        select = select
+            .filter(ContainerColumn::Status.is_in(statuses.iter().map(|status| status.as_str())));
+    }
+    for (key, value) in &labels {
+        select = select.filter(
+            Expr::col(ContainerColumn::Labels)
+                .like(LikeExpr::new(label_like_pattern(key, value)).escape('\\')),
+        );
+    }
+    if let Some(image) = query.image.as_deref().filter(|image| !image.is_empty()) {
+        select = match image.strip_suffix('*') {
+            Some(prefix) => select.filter(
+                Expr::col(ContainerColumn::Image)
+                    .like(LikeExpr::new(prefix_like_pattern(prefix)).escape('\\')),
+            ),
+            None => select.filter(ContainerColumn::Image.eq(image)),
+        };
+    }
+
+    // Order by a unique key as well so pages stay stable
+    let select = select
+        .order_by(sort_column, sort_order.clone())
+        .order_by(ContainerColumn::Id, sort_order);
+
+    let total = select
+        .clone()
+        .paginate(&state.db, limit)
+        .num_items()
+        .await?;
 
-    let responses: Vec<ContainerResponse> = containers
+    let containers = select.offset(offset).limit(limit).all(&state.db).await?;
+
+    let items: Vec<ContainerResponse> = containers
         .into_iter()
         .map(|container| container.into())
         .collect();
 
-    Ok((StatusCode::OK, Json(responses)))
+    Ok((
+        StatusCode::OK,
+        Json(ContainerListResponse {
+            items,
+            total,
+            limit,
+            offset,
+        }),
+    ))
 }
 
+#[utoipa::path(
+    get,
+    path = "/v1/containers/{id}",
+    tag = "containers",
+    params(("id" = String, Path, description = "Container id"), GetContainerQuery),
+    responses(
+        (status = 200, description = "The container", body = ContainerResponse),
+        (status = 404, description = "Container not found", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse)
+    )
+)]
 pub async fn get_container(
     State(state): State<AppState>,
     Path(container_id): Path<String>,
-) -> Result<(StatusCode, Json<ContainerResponse>), (StatusCode, Json<serde_json::Value>)> {
-    let container = ContainerEntity::find_by_id(container_id.clone())
-        .one(&state.db)
-        .await
-        .map_err(|e| {
-            error!("Failed to fetch container: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({ "error": "Database error" })),
-            )
-        })?
-        .ok_or_else(|| {
-            (
-                StatusCode::NOT_FOUND,
-                Json(json!({ "error": "Container not found" })),
-            )
-        })?;
+    Query(query): Query<GetContainerQuery>,
+) -> Result<(StatusCode, Json<ContainerResponse>), ApiError> {
+    let container = find_container_including_deleted(
+        &state.db,
+        &container_id,
+        query.include_deleted.unwrap_or(false),
+    )
+    .await?;
 
     let response: ContainerResponse = container.into();
 
     Ok((StatusCode::OK, Json(response)))
 }
 
-pub async fn delete_container(
+#[utoipa::path(
+    patch,
+    path = "/v1/containers/{id}",
+    tag = "containers",
+    params(("id" = String, Path, description = "Container id")),
+    request_body = UpdateContainerRequest,
+    responses(
+        (status = 200, description = "The updated container", body = ContainerResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Container not found", body = ErrorResponse),
+        (status = 409, description = "Name in use or container is being removed", body = ErrorResponse),
+        (status = 422, description = "Field validation failed", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse)
+    )
+)]
+pub async fn update_container(
+    State(state): State<AppState>,
+    Path(container_id): Path<String>,
+    ApiJson(request): ApiJson<UpdateContainerRequest>,
+) -> Result<(StatusCode, Json<ContainerResponse>), ApiError> {
+    request.validate_fields().map_err(ApiError::Validation)?;
+    request.validate().map_err(ApiError::InvalidRequest)?;
+
+    let container = find_container(&state.db, &container_id).await?;
+
+    if container.status == "Removing" {
+        return Err(ApiError::InvalidState(
+            "Container is being removed and cannot be updated".to_string(),
+        ));
+    }
+
+    let now = chrono::Utc::now();
+    let mut active_model = container.clone().into_active_model();
+
+    let name = request.name.filter(|name| *name != container.name);
+    if let Some(name) = &name {
+        if name_in_use(&state.db, name, Some(&container.id)).await? {
+            return Err(ApiError::NameConflict);
+        }
+        active_model.name = Set(name.clone());
+    }
+
+    // Changing the image requires a new Docker container. One that is up is recreated right
+    // away, any other keeps its status and is recreated through its changed spec once started
+    let image = request.image.filter(|image| *image != container.image);
+    if let Some(image) = &image {
+        active_model.image = Set(image.clone());
+        active_model.image_digest = Set(None);
+        if matches!(container.status.as_str(), "Created" | "Running" | "Paused") {
+            info!(
+                "Image changed for container {}, scheduling recreate",
+                container_id
+            );
+            active_model.status = Set("Pending".to_string());
+            active_model.status_changed_at = Set(Some(now));
+            active_model.error_message = Set(None);
+            active_model.restart_count = Set(0);
+        }
+    }
+
+    // Status stays as is, the processor notices the changed spec and recreates the container
+    let env = request
+        .env
+        .and_then(|env| serde_json::to_string(&env).ok())
+        .filter(|env| container.env.as_ref() != Some(env));
+    if let Some(env) = &env {
+        active_model.env = Set(Some(env.clone()));
+    }
+
+    // Nothing differs, so there is nothing to write or record
+    let changed: Vec<&str> = [
+        ("Name", name.is_some()),
+        ("Image", image.is_some()),
+        ("Env", env.is_some()),
+    ]
+    .into_iter()
+    .filter(|(_, changed)| *changed)
+    .map(|(field, _)| field)
+    .collect();
+    if changed.is_empty() {
+        return Ok((StatusCode::OK, Json(container.into())));
+    }
+    // e.g. "Name changed" or "Name, Image changed"
+    let reason = format!("{} changed", changed.join(", "));
+
+    active_model.updated_at = Set(now);
+
+    // Docker is renamed right away instead of on a recreate. There is no transaction spanning
+    // both, so the Docker rename is undone when the row can't be updated
+    let renamed = match (&name, &container.docker_id) {
+        (Some(name), Some(docker_id)) => {
+            match state.docker.rename_container(docker_id, name).await {
+                Ok(()) => Some(docker_id),
+                // The processor deals with the missing container, the row can still be renamed
+                Err(e) if is_container_gone(&e) => None,
+                Err(e)
+                    if matches!(
+                        e.downcast_ref::<DockerError>(),
+                        Some(DockerError::NameConflict(_))
+                    ) =>
+                {
+                    return Err(ApiError::NameConflict);
+                }
+                Err(e) => return Err(ApiError::Docker(e)),
+            }
+        }
+        _ => None,
+    };
+
+    let result = async {
+        let txn = state.db.begin().await?;
+        let updated = update_if_unchanged(&txn, &container, active_model).await?;
+        audit_log::record_transition(
+            &txn,
+            &container_id,
+            Some(&container.status),
+            &updated.status,
+            Some(reason),
+        )
+        .await?;
+        txn.commit().await?;
+        Ok::<_, ApiError>(updated)
+    }
+    .await;
+    let updated = match result {
+        Ok(updated) => updated,
+        Err(e) => {
+            if let Some(docker_id) = renamed {
+                if let Err(undo_error) = state
+                    .docker
+                    .rename_container(docker_id, &container.name)
+                    .await
+                {
+                    warn!(
+                        "Failed to rename Docker container {} back to {}: {}",
+                        docker_id, container.name, undo_error
+                    );
+                }
+            }
+            return Err(e);
+        }
+    };
+    publish_status(&state, &updated);
+
+    info!("Container updated successfully: {}", container_id);
+    Ok((StatusCode::OK, Json(updated.into())))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/containers/{id}/actions",
+    tag = "containers",
+    params(("id" = String, Path, description = "Container id")),
+    request_body = ContainerActionRequest,
+    responses(
+        (status = 200, description = "Action accepted", body = ContainerResponse),
+        (status = 404, description = "Container not found", body = ErrorResponse),
+        (status = 409, description = "Action not allowed in the current status", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse)
+    )
+)]
+pub async fn container_action(
     State(state): State<AppState>,
     Path(container_id): Path<String>,
-) -> Result<(StatusCode, Json<serde_json::Value>), (StatusCode, Json<serde_json::Value>)> {
-    let container = ContainerEntity::find_by_id(container_id.clone())
-        .one(&state.db)
-        .await
-        .map_err(|e| {
-            error!("Failed to fetch container: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({ "error": "Database error" })),
-            )
-        })?
-        .ok_or_else(|| {
-            (
-                StatusCode::NOT_FOUND,
-                Json(json!({ "error": "Container not found" })),
-            )
+    ApiJson(request): ApiJson<ContainerActionRequest>,
+) -> Result<(StatusCode, Json<ContainerResponse>), ApiError> {
+    let container = find_container(&state.db, &container_id).await?;
+
+    // Only record the desired status - processor will perform the Docker operations
+    let next_status = match (request.action, container.status.as_str()) {
+        // Restarts stop the container first, which makes Docker remove it
+        (ContainerAction::Restart, _) if container.auto_remove => {
+            return Err(ApiError::InvalidState(
+                "Cannot restart a container with auto_remove".to_string(),
+            ));
+        }
+        (ContainerAction::Stop, "Running" | "Restarting" | "Paused") => "Stopped",
+        (ContainerAction::Start | ContainerAction::Restart, "Stopped") => {
+            match container.docker_id {
+                Some(_) => "Created",
+                None => "Pending",
+            }
+        }
+        (ContainerAction::Start, "Failed") => "Pending",
+        (ContainerAction::Restart, "Running") => "Restarting",
+        (ContainerAction::Pause, "Running") => "Paused",
+        (ContainerAction::Unpause, "Paused") => "Running",
+        (action, status) => {
+            return Err(ApiError::InvalidState(format!(
+                "Cannot {} container in {} status",
+                action.as_str(),
+                status
+            )));
+        }
+    };
+
+    // Pausing is immediate in Docker, so unlike the other actions it is applied right away.
+    // Leaving it to the processor would look like someone else paused the container
+    if matches!(
+        request.action,
+        ContainerAction::Pause | ContainerAction::Unpause
+    ) {
+        let docker_id = container.docker_id.as_deref().ok_or_else(|| {
+            ApiError::InvalidState("Container has not been created in Docker yet".to_string())
         })?;
+        let result = match request.action {
+            ContainerAction::Pause => state.docker.pause_container(docker_id).await,
+            _ => state.docker.unpause_container(docker_id).await,
+        };
+        result.map_err(ApiError::Docker)?;
+    }
+
+    let now = chrono::Utc::now();
+    let mut active_model = container.clone().into_active_model();
+    active_model.status = Set(next_status.to_string());
+    active_model.status_changed_at = Set(Some(now));
+    active_model.error_message = Set(None);
+    // A manual start gives the restart policy a fresh retry budget
+    if request.action == ContainerAction::Start {
+        active_model.restart_count = Set(0);
+    }
+    active_model.updated_at = Set(now);
+
+    let txn = state.db.begin().await?;
+    let updated = match update_if_unchanged(&txn, &container, active_model).await {
+        Ok(updated) => updated,
+        // The processor may have noticed the pause in Docker and recorded it first
+        Err(ApiError::ConcurrentModification) => {
+            txn.rollback().await?;
+            let current = find_container(&state.db, &container_id).await?;
+            if current.status == next_status {
+                return Ok((StatusCode::OK, Json(current.into())));
+            }
+            return Err(ApiError::ConcurrentModification);
+        }
+        Err(e) => return Err(e),
+    };
+    audit_log::record_transition(
+        &txn,
+        &container_id,
+        Some(&container.status),
+        next_status,
+        Some(format!("{} requested", request.action.as_str())),
+    )
+    .await?;
+    txn.commit().await?;
+    publish_status(&state, &updated);
+
+    info!(
+        "Container {} action {} accepted, status set to {}",
+        container_id,
+        request.action.as_str(),
+        next_status
+    );
+    Ok((StatusCode::OK, Json(updated.into())))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/containers/{id}/reconcile",
+    tag = "containers",
+    params(("id" = String, Path, description = "Container id")),
+    responses(
+        (status = 200, description = "Container reconciled, current state returned", body = ContainerResponse),
+        (status = 404, description = "Container not found", body = ErrorResponse),
+        (status = 500, description = "Reconciliation failed", body = ErrorResponse),
+        (status = 503, description = "Processor is not running", body = ErrorResponse)
+    )
+)]
+pub async fn reconcile_container(
+    State(state): State<AppState>,
+    Path(container_id): Path<String>,
+) -> Result<Json<ContainerResponse>, ApiError> {
+    find_container(&state.db, &container_id).await?;
+    // Requests sent before startup reconciliation would wait for Docker indefinitely
+    if !state.readiness.borrow().initialized {
+        return Err(ApiError::ProcessorUnavailable);
+    }
+
+    let (done, result) = oneshot::channel();
+    state
+        .reconcile_requests
+        .send(ReconcileRequest {
+            container_id: container_id.clone(),
+            done,
+        })
+        .await
+        .map_err(|_| ApiError::ProcessorUnavailable)?;
+    result
+        .await
+        .map_err(|_| ApiError::ProcessorUnavailable)?
+        .map_err(ApiError::ReconcileFailed)?;
+
+    // A Removing container is gone afterwards, report it as Removed rather than not found.
+    // Containers another processor is working on are returned unchanged
+    let container = find_container_including_deleted(&state.db, &container_id, true).await?;
+    info!(
+        "Container {} reconciled on request, status {}",
+        container_id, container.status
+    );
+    Ok(Json(container.into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/containers/{id}/logs",
+    tag = "containers",
+    params(("id" = String, Path, description = "Container id"), ContainerLogsQuery),
+    responses(
+        (status = 200, description = "Log lines, or a text/event-stream when follow=true. Removed containers answer from the log archive when enabled, without following", body = ContainerLogsResponse),
+        (status = 404, description = "Container not found, or removed without archived logs", body = ErrorResponse),
+        (status = 409, description = "Container has no Docker container yet", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse)
+    )
+)]
+pub async fn get_container_logs(
+    State(state): State<AppState>,
+    Path(container_id): Path<String>,
+    Query(query): Query<ContainerLogsQuery>,
+) -> Result<Response, ApiError> {
+    let container = find_container_including_deleted(&state.db, &container_id, true).await?;
+
+    let tail = query.tail.unwrap_or(DEFAULT_LOG_TAIL);
+    let timestamps = query.timestamps.unwrap_or(false);
+
+    // Docker no longer has removed containers, nor failed ones once cleaned up. Only the
+    // archive may
+    if container.deleted_at.is_some() || container.docker_id.is_none() {
+        if let Some(lines) = archived_logs(&state, &container.id, tail, timestamps).await? {
+            return Ok(logs_response(container.id, lines));
+        }
+    }
+    if container.deleted_at.is_some() {
+        return Err(ApiError::ContainerNotFound);
+    }
+    let docker_id = container.docker_id.ok_or_else(|| {
+        ApiError::InvalidState("Container has not been created in Docker yet".to_string())
+    })?;
+
+    if query.follow.unwrap_or(false) {
+        // Only the Docker stream lives on - the DB connection is released once the lookup is done
+        info!("Streaming logs for container {}", container_id);
+        let events = state
+            .docker
+            .follow_container_logs(&docker_id, tail, timestamps)
+            .take_while(|line| futures::future::ready(line.is_ok()))
+            .filter_map(|line| futures::future::ready(line.ok()))
+            .map(|line| Ok::<_, Infallible>(Event::default().data(line)));
+
+        return Ok(Sse::new(events)
+            .keep_alive(KeepAlive::default())
+            .into_response());
+    }
+
+    let lines = match state
+        .docker
+        .get_container_logs(&docker_id, tail, timestamps)
+        .await
+    {
+        Ok(lines) => lines,
+        // Gone from Docker before the processor noticed, e.g. through auto_remove
+        Err(e) if is_container_gone(&e) => {
+            match archived_logs(&state, &container.id, tail, timestamps).await? {
+                Some(lines) => lines,
+                None => return Err(ApiError::Docker(e)),
+            }
+        }
+        Err(e) => return Err(ApiError::Docker(e)),
+    };
+
+    Ok(logs_response(container.id, lines))
+}
+
+// None when archiving is off or nothing was archived for the container
+async fn archived_logs(
+    state: &AppState,
+    container_id: &str,
+    tail: usize,
+    timestamps: bool,
+) -> Result<Option<Vec<String>>, ApiError> {
+    let Some(log_archive) = &state.log_archive else {
+        return Ok(None);
+    };
+    log_archive
+        .read(container_id, tail, timestamps)
+        .await
+        .map_err(ApiError::LogArchive)
+}
+
+fn logs_response(id: String, lines: Vec<String>) -> Response {
+    (StatusCode::OK, Json(ContainerLogsResponse { id, lines })).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/logs",
+    tag = "containers",
+    params(AggregateLogsQuery),
+    responses(
+        (status = 200, description = "Log lines of all containers matching the labels, merged by timestamp", body = AggregateLogsResponse),
+        (status = 400, description = "Missing or invalid label selector, or too many matching containers", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse)
+    )
+)]
+pub async fn get_aggregate_logs(
+    State(state): State<AppState>,
+    Query(query): Query<AggregateLogsQuery>,
+) -> Result<(StatusCode, Json<AggregateLogsResponse>), ApiError> {
+    let labels = query.labels().map_err(ApiError::InvalidRequest)?;
+    let tail = query.tail.unwrap_or(DEFAULT_LOG_TAIL);
+    let timestamps = query.timestamps.unwrap_or(false);
+
+    let mut select = ContainerEntity::find().filter(ContainerColumn::DeletedAt.is_null());
+    for (key, value) in &labels {
+        select = select.filter(
+            Expr::col(ContainerColumn::Labels)
+                .like(LikeExpr::new(label_like_pattern(key, value)).escape('\\')),
+        );
+    }
+    let containers = select
+        .order_by_asc(ContainerColumn::Name)
+        .limit(MAX_AGGREGATE_LOG_CONTAINERS as u64 + 1)
+        .all(&state.db)
+        .await?;
+    if containers.len() > MAX_AGGREGATE_LOG_CONTAINERS {
+        return Err(ApiError::InvalidRequest(format!(
+            "label matches more than {} containers",
+            MAX_AGGREGATE_LOG_CONTAINERS
+        )));
+    }
+
+    let fetched = futures::future::try_join_all(
+        containers
+            .iter()
+            .map(|container| container_log_lines(&state, container, tail)),
+    )
+    .await?;
+
+    let mut lines = Vec::new();
+    for (container, container_lines) in containers.iter().zip(fetched) {
+        let mut previous = DateTime::<Utc>::MIN_UTC;
+        for line in container_lines {
+            // Lines without a timestamp stay behind the line before them
+            let (timestamp, message) = match line.split_once(' ') {
+                Some((prefix, message)) => match DateTime::parse_from_rfc3339(prefix) {
+                    Ok(timestamp) => (timestamp.with_timezone(&Utc), message),
+                    Err(_) => (previous, line.as_str()),
+                },
+                None => (previous, line.as_str()),
+            };
+            previous = timestamp;
+            lines.push((
+                timestamp,
+                AggregateLogLine {
+                    container_id: container.id.clone(),
+                    container_name: container.name.clone(),
+                    line: match timestamps {
+                        true => line.clone(),
+                        false => message.to_string(),
+                    },
+                },
+            ));
+        }
+    }
+    // Stable, so lines of one container sharing a timestamp keep their order
+    lines.sort_by_key(|(timestamp, _)| *timestamp);
+
+    let mut bytes = 0;
+    let kept = lines
+        .iter()
+        .rev()
+        .take_while(|(_, line)| {
+            bytes += line.line.len();
+            bytes <= MAX_AGGREGATE_LOG_BYTES
+        })
+        .count();
+    let truncated = kept < lines.len();
+    let lines = lines
+        .split_off(lines.len() - kept)
+        .into_iter()
+        .map(|(_, line)| line)
+        .collect();
+
+    Ok((
+        StatusCode::OK,
+        Json(AggregateLogsResponse {
+            containers: containers.len() as u64,
+            lines,
+            truncated,
+        }),
+    ))
+}
+
+// Timestamped lines from Docker, or from the archive once Docker no longer has the container.
+// Empty when neither has any
+async fn container_log_lines(
+    state: &AppState,
+    container: &ContainerModel,
+    tail: usize,
+) -> Result<Vec<String>, ApiError> {
+    if let Some(docker_id) = &container.docker_id {
+        match state.docker.get_container_logs(docker_id, tail, true).await {
+            Ok(lines) => return Ok(lines),
+            Err(e) if is_container_gone(&e) => {}
+            Err(e) => return Err(ApiError::Docker(e)),
+        }
+    }
+    Ok(archived_logs(state, &container.id, tail, true)
+        .await?
+        .unwrap_or_default())
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/containers/{id}/stats",
+    tag = "containers",
+    params(("id" = String, Path, description = "Container id")),
+    responses(
+        (status = 200, description = "Resource usage snapshot", body = ContainerStats),
+        (status = 404, description = "Container not found", body = ErrorResponse),
+        (status = 409, description = "Container is not running", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse)
+    )
+)]
+pub async fn get_container_stats(
+    State(state): State<AppState>,
+    Path(container_id): Path<String>,
+) -> Result<(StatusCode, Json<ContainerStats>), ApiError> {
+    let container = find_container(&state.db, &container_id).await?;
+
+    let docker_id = match (container.status.as_str(), container.docker_id) {
+        ("Running", Some(docker_id)) => docker_id,
+        _ => {
+            return Err(ApiError::InvalidState(
+                "Container is not running".to_string(),
+            ));
+        }
+    };
+
+    let stats = state
+        .docker
+        .get_container_stats(&docker_id)
+        .await
+        .map_err(ApiError::Docker)?;
+
+    Ok((StatusCode::OK, Json(stats)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/containers/{id}/top",
+    tag = "containers",
+    params(("id" = String, Path, description = "Container id")),
+    responses(
+        (status = 200, description = "Processes running in the container", body = ContainerTop),
+        (status = 404, description = "Container not found", body = ErrorResponse),
+        (status = 409, description = "Container is not running", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse)
+    )
+)]
+pub async fn get_container_top(
+    State(state): State<AppState>,
+    Path(container_id): Path<String>,
+) -> Result<(StatusCode, Json<ContainerTop>), ApiError> {
+    let container = find_container(&state.db, &container_id).await?;
+
+    let docker_id = match (container.status.as_str(), container.docker_id) {
+        ("Running", Some(docker_id)) => docker_id,
+        _ => {
+            return Err(ApiError::InvalidState(
+                "Container is not running".to_string(),
+            ));
+        }
+    };
+
+    let top = state
+        .docker
+        .top(&docker_id)
+        .await
+        .map_err(ApiError::Docker)?;
+
+    Ok((StatusCode::OK, Json(top)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/containers/{id}/inspect",
+    tag = "containers",
+    params(("id" = String, Path, description = "Container id")),
+    responses(
+        (status = 200, description = "Raw Docker inspect output", body = Object),
+        (status = 404, description = "Container not found", body = ErrorResponse),
+        (status = 409, description = "Container has no Docker container yet", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse)
+    )
+)]
+pub async fn inspect_container(
+    State(state): State<AppState>,
+    Path(container_id): Path<String>,
+) -> Result<(StatusCode, Json<ContainerInspectResponse>), ApiError> {
+    let container = find_container(&state.db, &container_id).await?;
+
+    let Some(docker_id) = container.docker_id else {
+        return Err(ApiError::InvalidState(
+            "Container has not been created in Docker yet".to_string(),
+        ));
+    };
+
+    let inspect = state
+        .docker
+        .inspect_container(&docker_id)
+        .await
+        .map_err(ApiError::Docker)?;
+
+    Ok((StatusCode::OK, Json(inspect)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/containers/{id}/exec",
+    tag = "containers",
+    params(("id" = String, Path, description = "Container id")),
+    request_body = ExecRequest,
+    responses(
+        (status = 200, description = "Command output and exit code", body = ExecResponse),
+        (status = 404, description = "Container not found", body = ErrorResponse),
+        (status = 409, description = "Container is not running", body = ErrorResponse),
+        (status = 422, description = "Field validation failed", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse)
+    )
+)]
+pub async fn exec_container(
+    State(state): State<AppState>,
+    Path(container_id): Path<String>,
+    ApiJson(request): ApiJson<ExecRequest>,
+) -> Result<(StatusCode, Json<ExecResponse>), ApiError> {
+    request.validate_fields().map_err(ApiError::Validation)?;
+
+    let container = find_container(&state.db, &container_id).await?;
+
+    let docker_id = match (container.status.as_str(), container.docker_id) {
+        ("Running", Some(docker_id)) => docker_id,
+        _ => {
+            return Err(ApiError::InvalidState(
+                "Container is not running".to_string(),
+            ));
+        }
+    };
+
+    info!(
+        "Running exec in container {}: {:?}",
+        container_id, request.cmd
+    );
+    let response = state
+        .docker
+        .exec(&docker_id, request.cmd, state.config.exec_max_output_bytes)
+        .await
+        .map_err(ApiError::Docker)?;
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/containers/{id}",
+    tag = "containers",
+    params(("id" = String, Path, description = "Container id")),
+    responses(
+        (status = 200, description = "Container marked for removal", body = Object),
+        (status = 404, description = "Container not found", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse)
+    )
+)]
+pub async fn delete_container(
+    State(state): State<AppState>,
+    Path(container_id): Path<String>,
+) -> Result<(StatusCode, Json<serde_json::Value>), ApiError> {
+    let container = find_container(&state.db, &container_id).await?;
 
     // Mark container for removal - processor will handle actual Docker operations
-    let mut active_model = container.into_active_model();
+    let now = chrono::Utc::now();
+    let mut active_model = container.clone().into_active_model();
     active_model.status = Set("Removing".to_string());
-    active_model.updated_at = Set(chrono::Utc::now().to_rfc3339());
+    active_model.status_changed_at = Set(Some(now));
+    active_model.updated_at = Set(now);
 
-    active_model.update(&state.db).await.map_err(|e| {
-        error!("Failed to mark container for removal: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "error": "Database error" })),
-        )
-    })?;
+    let txn = state.db.begin().await?;
+    let updated = update_if_unchanged(&txn, &container, active_model).await?;
+    audit_log::record_transition(
+        &txn,
+        &container_id,
+        Some(&container.status),
+        "Removing",
+        Some("delete requested".to_string()),
+    )
+    .await?;
+    txn.commit().await?;
+    publish_status(&state, &updated);
 
     info!("Container marked for removal: {}", container_id);
     Ok((
@@ -137,3 +1421,1065 @@ pub async fn delete_container(
         Json(json!({ "message": "Container marked for removal" })),
     ))
 }
+
+#[utoipa::path(
+    delete,
+    path = "/v1/containers",
+    tag = "containers",
+    params(PruneContainersQuery),
+    responses(
+        (status = 200, description = "Matching containers marked for removal", body = PruneContainersResponse),
+        (status = 400, description = "Missing status or invalid query", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse)
+    )
+)]
+pub async fn prune_containers(
+    State(state): State<AppState>,
+    Query(query): Query<PruneContainersQuery>,
+) -> Result<(StatusCode, Json<PruneContainersResponse>), ApiError> {
+    let statuses = query.statuses().map_err(ApiError::InvalidRequest)?;
+    let older_than = query.older_than().map_err(ApiError::InvalidRequest)?;
+
+    let mut condition = Condition::all()
+        .add(ContainerColumn::Status.is_in(statuses.iter().map(|status| status.as_str())));
+    if let Some(older_than) = older_than {
+        condition = condition.add(ContainerColumn::UpdatedAt.lt(chrono::Utc::now() - older_than));
+    }
+
+    // One INSERT and one UPDATE in a single transaction. Writing the audit rows first takes
+    // the write lock on SQLite and locks the matching rows on Postgres, so the UPDATE below
+    // changes exactly the containers that were audited
+    let prune_id = uuid::Uuid::new_v4().to_string();
+    let now = chrono::Utc::now();
+    let txn = state.db.begin().await?;
+
+    let audit = SeaQuery::insert()
+        .into_table(AuditLogEntity)
+        .columns([
+            AuditLogColumn::Id,
+            AuditLogColumn::ContainerId,
+            AuditLogColumn::FromStatus,
+            AuditLogColumn::ToStatus,
+            AuditLogColumn::Timestamp,
+            AuditLogColumn::Reason,
+        ])
+        .select_from(
+            SeaQuery::select()
+                // Unique per container, and the prefix finds this prune's rows again below
+                .expr(
+                    Expr::val(format!("{}:", prune_id))
+                        .binary(BinOper::Custom("||"), Expr::col(ContainerColumn::Id)),
+                )
+                .column(ContainerColumn::Id)
+                .column(ContainerColumn::Status)
+                .expr(Expr::val("Removing"))
+                .expr(Expr::val(now.to_rfc3339_opts(SecondsFormat::Nanos, true)))
+                .expr(Expr::val("prune requested"))
+                .from(ContainerEntity)
+                .cond_where(condition.clone())
+                .lock(LockType::Update)
+                .to_owned(),
+        )
+        .map_err(|err| sea_orm::DbErr::Custom(err.to_string()))?
+        .to_owned();
+    txn.execute(state.db.get_database_backend().build(&audit))
+        .await?;
+
+    let result = ContainerEntity::update_many()
+        .col_expr(ContainerColumn::Status, Expr::value("Removing"))
+        .col_expr(ContainerColumn::StatusChangedAt, Expr::value(now))
+        .col_expr(ContainerColumn::UpdatedAt, Expr::value(now))
+        // Freezes the uptime of containers that were still up
+        .col_expr(
+            ContainerColumn::FinishedAt,
+            Func::coalesce([
+                Expr::col(ContainerColumn::FinishedAt).into(),
+                Expr::value(now),
+            ])
+            .into(),
+        )
+        .col_expr(
+            ContainerColumn::Version,
+            Expr::col(ContainerColumn::Version).add(1),
+        )
+        .filter(condition)
+        .filter(
+            ContainerColumn::Id.in_subquery(
+                SeaQuery::select()
+                    .column(AuditLogColumn::ContainerId)
+                    .from(AuditLogEntity)
+                    .and_where(AuditLogColumn::Id.like(format!("{}:%", prune_id)))
+                    .to_owned(),
+            ),
+        )
+        .exec(&txn)
+        .await?;
+    txn.commit().await?;
+    let count = result.rows_affected;
+
+    info!("Marked {} containers for removal by prune", count);
+    Ok((StatusCode::OK, Json(PruneContainersResponse { count })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/stats",
+    tag = "containers",
+    params(ClusterStatsQuery),
+    responses(
+        (status = 200, description = "Container counts and recent failures", body = ClusterStats),
+        (status = 400, description = "Invalid failures window", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse)
+    )
+)]
+pub async fn cluster_stats(
+    State(state): State<AppState>,
+    Query(query): Query<ClusterStatsQuery>,
+) -> Result<(StatusCode, Json<ClusterStats>), ApiError> {
+    let window = query.failures_window().map_err(ApiError::InvalidRequest)?;
+
+    let counts: Vec<(String, i64)> = ContainerEntity::find()
+        .select_only()
+        .column(ContainerColumn::Status)
+        .column_as(Expr::col(ContainerColumn::Id).count(), "count")
+        .filter(ContainerColumn::DeletedAt.is_null())
+        .group_by(ContainerColumn::Status)
+        .into_tuple()
+        .all(&state.db)
+        .await?;
+    let mut by_status: BTreeMap<String, u64> = ContainerStatus::ALL
+        .iter()
+        .filter(|status| **status != ContainerStatus::Removed)
+        .map(|status| (status.as_str().to_string(), 0))
+        .collect();
+    for (status, count) in counts {
+        by_status.insert(status, count as u64);
+    }
+
+    // Audit timestamps are fixed-width RFC 3339 in UTC, so they compare as strings
+    let since = (chrono::Utc::now() - window).to_rfc3339_opts(SecondsFormat::Nanos, true);
+    let failures = AuditLogEntity::find()
+        .filter(AuditLogColumn::ToStatus.eq("Failed"))
+        .filter(AuditLogColumn::Timestamp.gte(since))
+        .count(&state.db)
+        .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ClusterStats {
+            total: by_status.values().sum(),
+            by_status,
+            failures,
+            failures_window_secs: window.num_seconds(),
+        }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/containers/{id}/events",
+    tag = "containers",
+    params(("id" = String, Path, description = "Container id")),
+    responses(
+        (status = 200, description = "Status transitions, oldest first", body = ContainerEventsResponse),
+        (status = 404, description = "Container not found", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse)
+    )
+)]
+pub async fn get_container_events(
+    State(state): State<AppState>,
+    Path(container_id): Path<String>,
+) -> Result<(StatusCode, Json<ContainerEventsResponse>), ApiError> {
+    let events = AuditLogEntity::find()
+        .filter(AuditLogColumn::ContainerId.eq(container_id.clone()))
+        .order_by_asc(AuditLogColumn::Timestamp)
+        .order_by_asc(AuditLogColumn::Id)
+        .all(&state.db)
+        .await?;
+
+    // Removed containers keep their history, only unknown ids are a 404
+    if events.is_empty() {
+        find_container_including_deleted(&state.db, &container_id, true).await?;
+    }
+
+    Ok((
+        StatusCode::OK,
+        Json(ContainerEventsResponse {
+            id: container_id,
+            events: events
+                .into_iter()
+                .map(ContainerEventResponse::from)
+                .collect(),
+        }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/containers/{id}/watch",
+    tag = "containers",
+    params(("id" = String, Path, description = "Container id")),
+    responses(
+        (status = 101, description = "WebSocket sending the current status, then every status change", body = ContainerStatusUpdate),
+        (status = 404, description = "Container not found", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse)
+    )
+)]
+pub async fn watch_container(
+    State(state): State<AppState>,
+    Path(container_id): Path<String>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, ApiError> {
+    // Subscribing before the read means no change between the read and the upgrade is lost
+    let updates = state.status_updates.subscribe();
+    let container = find_container(&state.db, &container_id).await?;
+
+    info!("Watching container {}", container_id);
+    Ok(ws.on_upgrade(move |socket| watch_status(socket, state.db, container, updates)))
+}
+
+async fn watch_status(
+    mut socket: WebSocket,
+    db: DatabaseConnection,
+    container: ContainerModel,
+    mut updates: broadcast::Receiver<ContainerStatusUpdate>,
+) {
+    let mut current = ContainerStatusUpdate::from(&container);
+    if send_status_update(&mut socket, &current).await.is_err() {
+        return;
+    }
+
+    while current.status != ContainerStatus::Removed {
+        let update = tokio::select! {
+            update = updates.recv() => update,
+            // Clients don't send anything, this only notices them going away
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => continue,
+            },
+        };
+
+        let update = match update {
+            Ok(update) if update.id == current.id => update,
+            Ok(_) => continue,
+            Err(RecvError::Lagged(skipped)) => {
+                // The skipped updates may have included this container, so re-read it
+                warn!(
+                    "Watcher for container {} missed {} status updates",
+                    current.id, skipped
+                );
+                match ContainerEntity::find_by_id(current.id.clone())
+                    .one(&db)
+                    .await
+                {
+                    Ok(Some(container)) => ContainerStatusUpdate::from(&container),
+                    Ok(None) => return,
+                    Err(e) => {
+                        error!("Failed to re-read container {}: {}", current.id, e);
+                        return;
+                    }
+                }
+            }
+            Err(RecvError::Closed) => return,
+        };
+
+        if update.status == current.status {
+            continue;
+        }
+        if send_status_update(&mut socket, &update).await.is_err() {
+            return;
+        }
+        current = update;
+    }
+
+    // Removed is final, nothing more will be sent
+    let _ = socket.send(Message::Close(None)).await;
+}
+
+// Status changes made by the API reach watchers without waiting for the processor
+fn publish_status(state: &AppState, container: &ContainerModel) {
+    // Only fails when nobody is watching
+    let _ = state
+        .status_updates
+        .send(ContainerStatusUpdate::from(container));
+}
+
+async fn send_status_update(
+    socket: &mut WebSocket,
+    update: &ContainerStatusUpdate,
+) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(update).map_err(axum::Error::new)?;
+    socket.send(Message::Text(text)).await
+}
+
+async fn find_network(db: &DatabaseConnection, network_id: &str) -> Result<NetworkModel, ApiError> {
+    NetworkEntity::find_by_id(network_id.to_string())
+        .one(db)
+        .await?
+        .ok_or(ApiError::NetworkNotFound)
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/networks",
+    tag = "networks",
+    responses(
+        (status = 200, description = "Networks created through the API", body = NetworkListResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse)
+    )
+)]
+pub async fn list_networks(
+    State(state): State<AppState>,
+) -> Result<(StatusCode, Json<NetworkListResponse>), ApiError> {
+    let networks = NetworkEntity::find()
+        .order_by_asc(NetworkColumn::CreatedAt)
+        .all(&state.db)
+        .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(NetworkListResponse {
+            items: networks.into_iter().map(NetworkResponse::from).collect(),
+        }),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/networks",
+    tag = "networks",
+    request_body = CreateNetworkRequest,
+    responses(
+        (status = 201, description = "Network created in Docker", body = NetworkResponse),
+        (status = 409, description = "Network name already in use", body = ErrorResponse),
+        (status = 422, description = "Invalid fields", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse)
+    )
+)]
+pub async fn create_network(
+    State(state): State<AppState>,
+    ApiJson(request): ApiJson<CreateNetworkRequest>,
+) -> Result<(StatusCode, Json<NetworkResponse>), ApiError> {
+    info!("Creating network: {}", request.name);
+    request.validate_fields().map_err(ApiError::Validation)?;
+
+    let existing = NetworkEntity::find()
+        .filter(NetworkColumn::Name.eq(request.name.clone()))
+        .count(&state.db)
+        .await?;
+    if existing > 0 {
+        return Err(ApiError::NetworkNameConflict);
+    }
+
+    // Networks have no long-running state to reconcile, so Docker is called directly
+    let docker_id = match state
+        .docker
+        .create_network(&request.name, request.driver.as_deref())
+        .await
+    {
+        Ok(docker_id) => docker_id,
+        Err(e)
+            if matches!(
+                e.downcast_ref::<DockerError>(),
+                Some(DockerError::NetworkNameConflict(_))
+            ) =>
+        {
+            return Err(ApiError::NetworkNameConflict);
+        }
+        Err(e) => return Err(ApiError::Docker(e)),
+    };
+
+    let network = NetworkModel::new(request, docker_id);
+    if let Err(e) = NetworkEntity::insert(network.clone().into_active_model())
+        .exec(&state.db)
+        .await
+    {
+        // Don't leave a network behind that the API doesn't know about
+        if let Err(remove_error) = state.docker.remove_network(&network.docker_id).await {
+            warn!(
+                "Failed to remove untracked network {}: {}",
+                network.docker_id, remove_error
+            );
+        }
+        return match e.sql_err() {
+            Some(SqlErr::UniqueConstraintViolation(_)) => Err(ApiError::NetworkNameConflict),
+            _ => Err(e.into()),
+        };
+    }
+
+    info!("Network created successfully: {}", network.id);
+    Ok((StatusCode::CREATED, Json(network.into())))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/networks/{id}",
+    tag = "networks",
+    params(("id" = String, Path, description = "Network id")),
+    responses(
+        (status = 200, description = "Network removed", body = Object),
+        (status = 404, description = "Network not found", body = ErrorResponse),
+        (status = 409, description = "Network is still used by containers", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse)
+    )
+)]
+pub async fn delete_network(
+    State(state): State<AppState>,
+    Path(network_id): Path<String>,
+) -> Result<(StatusCode, Json<serde_json::Value>), ApiError> {
+    let network = find_network(&state.db, &network_id).await?;
+
+    // Containers can refer to the network by name or by Docker id
+    let in_use = ContainerEntity::find()
+        .filter(
+            Condition::any()
+                .add(ContainerColumn::Network.eq(network.name.clone()))
+                .add(ContainerColumn::Network.eq(network.docker_id.clone())),
+        )
+        .filter(ContainerColumn::DeletedAt.is_null())
+        .count(&state.db)
+        .await?;
+    if in_use > 0 {
+        return Err(ApiError::InvalidState(format!(
+            "Network {} is still used by {} containers",
+            network.name, in_use
+        )));
+    }
+
+    state
+        .docker
+        .remove_network(&network.docker_id)
+        .await
+        .map_err(ApiError::Docker)?;
+    NetworkEntity::delete_by_id(network.id.clone())
+        .exec(&state.db)
+        .await?;
+
+    info!("Network removed: {}", network.id);
+    Ok((
+        StatusCode::OK,
+        Json(json!({ "message": "Network removed" })),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/images",
+    tag = "images",
+    params(ListImagesQuery),
+    responses(
+        (status = 200, description = "Images available to the Docker daemon", body = ImageListResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse),
+        (status = 503, description = "Docker daemon unreachable", body = ErrorResponse)
+    )
+)]
+pub async fn list_images(
+    State(state): State<AppState>,
+    Query(query): Query<ListImagesQuery>,
+) -> Result<(StatusCode, Json<ImageListResponse>), ApiError> {
+    let items = state
+        .docker
+        .list_images(query.dangling.unwrap_or(false))
+        .await
+        .map_err(ApiError::Docker)?;
+
+    Ok((StatusCode::OK, Json(ImageListResponse { items })))
+}
+
+// Pulls run synchronously, the response only comes once the image is available
+#[utoipa::path(
+    post,
+    path = "/v1/images/pull",
+    tag = "images",
+    request_body = PullImageRequest,
+    responses(
+        (status = 200, description = "Image pulled", body = PullImageResponse),
+        (status = 400, description = "Malformed image reference", body = ErrorResponse),
+        (status = 404, description = "Image not found in the registry", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse),
+        (status = 502, description = "Registry rejected the configured credentials", body = ErrorResponse),
+        (status = 503, description = "Docker daemon unreachable", body = ErrorResponse),
+        (status = 504, description = "Pull timed out", body = ErrorResponse)
+    )
+)]
+pub async fn pull_image(
+    State(state): State<AppState>,
+    ApiJson(request): ApiJson<PullImageRequest>,
+) -> Result<(StatusCode, Json<PullImageResponse>), ApiError> {
+    request.validate().map_err(ApiError::InvalidRequest)?;
+
+    state
+        .docker
+        .pull_image(&request.image)
+        .await
+        .map_err(ApiError::Docker)?;
+    let digest = state
+        .docker
+        .image_digest(&request.image)
+        .await
+        .map_err(ApiError::Docker)?;
+
+    info!("Image pulled on request: {}", request.image);
+    Ok((
+        StatusCode::OK,
+        Json(PullImageResponse {
+            image: request.image,
+            digest,
+        }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/processors",
+    tag = "processors",
+    responses(
+        (status = 200, description = "Processors with their last heartbeat", body = ProcessorListResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse)
+    )
+)]
+pub async fn list_processors(
+    State(state): State<AppState>,
+) -> Result<(StatusCode, Json<ProcessorListResponse>), ApiError> {
+    let heartbeats = HeartbeatEntity::find()
+        .order_by_asc(HeartbeatColumn::Name)
+        .all(&state.db)
+        .await?;
+    let owned: Vec<(String, i64)> = ContainerEntity::find()
+        .select_only()
+        .column(ContainerColumn::Owner)
+        .column_as(Expr::col(ContainerColumn::Id).count(), "count")
+        .filter(ContainerColumn::Owner.is_not_null())
+        .group_by(ContainerColumn::Owner)
+        .into_tuple()
+        .all(&state.db)
+        .await?;
+
+    let now = chrono::Utc::now();
+    let items = heartbeats
+        .into_iter()
+        .map(|heartbeat| {
+            let age_secs = (now - heartbeat.last_seen).num_seconds();
+            let containers = owned
+                .iter()
+                .find(|(owner, _)| *owner == heartbeat.name)
+                .map_or(0, |(_, count)| *count as u64);
+            ProcessorResponse {
+                active: age_secs < state.config.owner_lease_secs as i64,
+                heartbeat_age_secs: age_secs,
+                containers,
+                name: heartbeat.name,
+                started_at: heartbeat.started_at,
+                last_seen: heartbeat.last_seen,
+            }
+        })
+        .collect();
+
+    Ok((StatusCode::OK, Json(ProcessorListResponse { items })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use std::sync::{Arc, Mutex};
+
+    use crate::db::run_migrations;
+    use crate::models::v1::FieldError;
+
+    // Docker points at a closed port, tests only reach handlers that don't call it
+    async fn test_state() -> AppState {
+        let mut config = Config::for_tests();
+        config.docker_host = Some("tcp://127.0.0.1:1".to_string());
+        let db = crate::db::establish_connection(&config).await.unwrap();
+        run_migrations(&db).await.unwrap();
+        AppState {
+            db,
+            docker: DockerService::new(&config).await.unwrap(),
+            config,
+            metrics: PrometheusBuilder::new().build_recorder().handle(),
+            readiness: watch::channel(Readiness::default()).1,
+            status_updates: broadcast::channel(16).0,
+            reconcile_requests: mpsc::channel(1).0,
+            log_archive: None,
+        }
+    }
+
+    async fn list_page(state: &AppState, limit: u64, offset: u64) -> ContainerListResponse {
+        let query = ListContainersQuery {
+            limit: Some(limit),
+            offset: Some(offset),
+            ..Default::default()
+        };
+        let (_, Json(page)) = list_containers(State(state.clone()), Query(query))
+            .await
+            .unwrap();
+        page
+    }
+
+    #[tokio::test]
+    async fn pages_cover_every_container_once() {
+        let state = test_state().await;
+        // The same created_at everywhere leaves only the id to keep the order stable
+        let created_at = chrono::Utc::now();
+        let containers: Vec<ContainerModel> = (0..120)
+            .map(|index| {
+                let mut container = ContainerModel::new(format!("web-{}", index), "nginx".into());
+                container.created_at = created_at;
+                container
+            })
+            .collect();
+        ContainerEntity::insert_many(
+            containers
+                .iter()
+                .map(|container| container.clone().into_active_model()),
+        )
+        .exec(&state.db)
+        .await
+        .unwrap();
+
+        let mut seen = Vec::new();
+        for (offset, expected) in [(0, 50), (50, 50), (100, 20), (120, 0)] {
+            let page = list_page(&state, 50, offset).await;
+            assert_eq!(page.total, 120);
+            assert_eq!(page.items.len(), expected, "page at offset {}", offset);
+            seen.extend(page.items.into_iter().map(|item| item.id));
+        }
+
+        let unique: HashSet<&String> = seen.iter().collect();
+        assert_eq!(unique.len(), seen.len(), "a container was listed twice");
+        let expected: HashSet<&String> = containers.iter().map(|container| &container.id).collect();
+        assert_eq!(unique, expected);
+    }
+
+    async fn listed_names(state: &AppState, query: ListContainersQuery) -> Vec<String> {
+        let (_, Json(page)) = list_containers(State(state.clone()), Query(query))
+            .await
+            .unwrap();
+        let mut names: Vec<String> = page.items.into_iter().map(|item| item.name).collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn containers_are_filtered_by_image() {
+        let state = test_state().await;
+        for (name, image, status) in [
+            ("web-1", "nginx:1.25", "Running"),
+            ("web-2", "nginx:1.27", "Pending"),
+            ("cache", "redis:7", "Running"),
+        ] {
+            let mut container = ContainerModel::new(name.into(), image.into());
+            container.status = status.to_string();
+            ContainerEntity::insert(container.into_active_model())
+                .exec(&state.db)
+                .await
+                .unwrap();
+        }
+        let query = |image: &str, status: Option<&str>| ListContainersQuery {
+            image: Some(image.to_string()),
+            status: status.map(str::to_string),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            listed_names(&state, query("redis:7", None)).await,
+            ["cache"]
+        );
+        assert_eq!(
+            listed_names(&state, query("nginx:1.25", None)).await,
+            ["web-1"]
+        );
+        // Exact matches don't extend to other tags
+        assert!(listed_names(&state, query("nginx", None)).await.is_empty());
+        assert_eq!(
+            listed_names(&state, query("nginx:*", None)).await,
+            ["web-1", "web-2"]
+        );
+        assert_eq!(
+            listed_names(&state, query("nginx:*", Some("Running"))).await,
+            ["web-1"]
+        );
+        assert!(listed_names(&state, query("redis:7", Some("Pending")))
+            .await
+            .is_empty());
+    }
+
+    async fn create(
+        state: &AppState,
+        dry_run: bool,
+        request: serde_json::Value,
+    ) -> Result<StatusCode, ApiError> {
+        let query = CreateContainerQuery {
+            dry_run: Some(dry_run),
+        };
+        let request = serde_json::from_value(request).unwrap();
+        let (status, _) = create_container(
+            State(state.clone()),
+            HeaderMap::new(),
+            Query(query),
+            ApiJson(request),
+        )
+        .await?;
+        Ok(status)
+    }
+
+    #[tokio::test]
+    async fn dry_run_reports_host_port_conflicts_like_create() {
+        let state = test_state().await;
+        let web = json!({
+            "name": "web",
+            "image": "nginx",
+            "ports": [{"container_port": 80, "host_port": 8080}]
+        });
+        assert_eq!(
+            create(&state, false, web).await.unwrap(),
+            StatusCode::CREATED
+        );
+
+        let other = json!({
+            "name": "other",
+            "image": "nginx",
+            "ports": [{"container_port": 80, "host_port": 8080}]
+        });
+        for dry_run in [true, false] {
+            let error = create(&state, dry_run, other.clone()).await.unwrap_err();
+            assert!(
+                matches!(&error, ApiError::PortConflict(port) if port == "8080/tcp"),
+                "dry_run={}: {:?}",
+                dry_run,
+                error
+            );
+        }
+
+        // The same number over another protocol is a different binding
+        let udp = json!({
+            "name": "other",
+            "image": "nginx",
+            "ports": [{"container_port": 80, "host_port": 8080, "protocol": "udp"}]
+        });
+        assert_eq!(create(&state, true, udp).await.unwrap(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn dry_run_does_not_contact_docker() {
+        // Docker is unreachable here, a network lookup would fail the request
+        let state = test_state().await;
+        let request = json!({"name": "web", "image": "nginx", "network": "backend"});
+        assert_eq!(
+            create(&state, true, request.clone()).await.unwrap(),
+            StatusCode::OK
+        );
+        assert!(matches!(
+            create(&state, false, request).await,
+            Err(ApiError::Docker(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn batch_reports_host_port_conflicts() {
+        let state = test_state().await;
+        let request = |name: &str, host_port: u32| {
+            json!({
+                "name": name,
+                "image": "nginx",
+                "ports": [{"container_port": 80, "host_port": host_port}]
+            })
+        };
+        create(&state, false, request("web", 8080)).await.unwrap();
+
+        let requests = [
+            request("api", 8080),
+            request("admin", 9090),
+            request("metrics", 9090),
+        ]
+        .map(|request| serde_json::from_value(request).unwrap());
+        let (status, Json(response)) =
+            create_containers_batch(State(state.clone()), ApiJson(requests.to_vec()))
+                .await
+                .unwrap();
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let failures: Vec<(usize, String)> = response
+            .items
+            .into_iter()
+            .map(|item| (item.index, item.error.unwrap().code))
+            .collect();
+        assert_eq!(
+            failures,
+            vec![
+                (0, "host_port_conflict".to_string()),
+                (2, "host_port_conflict".to_string())
+            ]
+        );
+        assert_eq!(ContainerEntity::find().count(&state.db).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn failed_container_frees_its_host_port() {
+        let state = test_state().await;
+        let request = |name: &str, host_port: u32| {
+            json!({
+                "name": name,
+                "image": "nginx",
+                "ports": [{"container_port": 80, "host_port": host_port}]
+            })
+        };
+        create(&state, false, request("web", 8080)).await.unwrap();
+        create(&state, false, request("admin", 80)).await.unwrap();
+
+        // Port 80 is taken, 8080 only while web holds a binding
+        assert!(matches!(
+            create(&state, true, request("other", 80)).await,
+            Err(ApiError::PortConflict(_))
+        ));
+        ContainerEntity::update_many()
+            .col_expr(ContainerColumn::Status, Expr::value("Failed"))
+            .filter(ContainerColumn::Name.eq("web"))
+            .exec(&state.db)
+            .await
+            .unwrap();
+        assert_eq!(
+            create(&state, false, request("other", 8080)).await.unwrap(),
+            StatusCode::CREATED
+        );
+    }
+
+    #[tokio::test]
+    async fn image_change_only_recreates_containers_that_are_up() {
+        let state = test_state().await;
+        let mut ids = Vec::new();
+        for (name, status) in [("web", "Running"), ("job", "Stopped")] {
+            let mut container = ContainerModel::new(name.into(), "nginx:1.25".into());
+            container.status = status.to_string();
+            ids.push(container.id.clone());
+            ContainerEntity::insert(container.into_active_model())
+                .exec(&state.db)
+                .await
+                .unwrap();
+        }
+
+        let mut updated = Vec::new();
+        for id in &ids {
+            let request = serde_json::from_value(json!({"image": "nginx:1.27"})).unwrap();
+            let (_, Json(container)) =
+                update_container(State(state.clone()), Path(id.clone()), ApiJson(request))
+                    .await
+                    .unwrap();
+            updated.push((container.image, container.status));
+        }
+        assert_eq!(
+            updated,
+            [
+                ("nginx:1.27".to_string(), ContainerStatus::Pending),
+                ("nginx:1.27".to_string(), ContainerStatus::Stopped)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn prune_marks_and_audits_only_matching_containers() {
+        let state = test_state().await;
+        let old = chrono::Utc::now() - chrono::Duration::hours(2);
+        let mut ids = BTreeMap::new();
+        for (name, status, updated_at) in [
+            ("old-stopped", "Stopped", old),
+            ("old-failed", "Failed", old),
+            ("new-stopped", "Stopped", chrono::Utc::now()),
+            ("old-running", "Running", old),
+        ] {
+            let mut container = ContainerModel::new(name.into(), "nginx".into());
+            container.status = status.to_string();
+            container.updated_at = updated_at;
+            ids.insert(container.id.clone(), name);
+            ContainerEntity::insert(container.into_active_model())
+                .exec(&state.db)
+                .await
+                .unwrap();
+        }
+
+        let query = PruneContainersQuery {
+            status: Some("Stopped,Failed".to_string()),
+            older_than: Some("1h".to_string()),
+        };
+        let (_, Json(response)) = prune_containers(State(state.clone()), Query(query))
+            .await
+            .unwrap();
+        assert_eq!(response.count, 2);
+
+        let mut statuses: Vec<(&str, String)> = ContainerEntity::find()
+            .all(&state.db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|container| (ids[&container.id], container.status))
+            .collect();
+        statuses.sort();
+        assert_eq!(
+            statuses,
+            [
+                ("new-stopped", "Stopped".to_string()),
+                ("old-failed", "Removing".to_string()),
+                ("old-running", "Running".to_string()),
+                ("old-stopped", "Removing".to_string())
+            ]
+        );
+
+        let mut audited: Vec<(&str, Option<String>, String)> = AuditLogEntity::find()
+            .all(&state.db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|event| (ids[&event.container_id], event.from_status, event.to_status))
+            .collect();
+        audited.sort();
+        assert_eq!(
+            audited,
+            [
+                (
+                    "old-failed",
+                    Some("Failed".to_string()),
+                    "Removing".to_string()
+                ),
+                (
+                    "old-stopped",
+                    Some("Stopped".to_string()),
+                    "Removing".to_string()
+                )
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn overlong_name_is_a_field_error() {
+        let state = test_state().await;
+        let request = json!({"name": "a".repeat(300), "image": "nginx"});
+
+        let error = create(&state, false, request).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let ApiError::Validation(fields) = error else {
+            panic!("expected a validation error, got {:?}", error);
+        };
+        assert_eq!(
+            fields,
+            vec![FieldError::new("name", "must be at most 255 characters")]
+        );
+        assert_eq!(ContainerEntity::find().count(&state.db).await.unwrap(), 0);
+    }
+
+    // Answers the two Docker calls list_images needs and keeps the request targets. With
+    // the dangling filter only the untagged image comes back, as Docker would filter.
+    async fn fake_image_daemon() -> (String, Arc<Mutex<Vec<String>>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let host = format!("tcp://{}", listener.local_addr().unwrap());
+        let targets = Arc::new(Mutex::new(Vec::new()));
+        let seen = targets.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut head = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+                    let read = stream.read(&mut buf).await.unwrap();
+                    if read == 0 {
+                        break;
+                    }
+                    head.extend_from_slice(&buf[..read]);
+                }
+                let head = String::from_utf8_lossy(&head);
+                let target = head.split(' ').nth(1).unwrap_or_default().to_string();
+                seen.lock().unwrap().push(target.clone());
+
+                let image = |id: &str, tags: Vec<&str>| {
+                    json!({
+                        "Id": id, "ParentId": "", "RepoTags": tags, "RepoDigests": [],
+                        "Created": 1_700_000_000, "Size": 1024, "SharedSize": -1,
+                        "Labels": {}, "Containers": -1
+                    })
+                };
+                let body = if target.starts_with("/version") {
+                    json!({"Version": "fake"})
+                } else if target.contains("dangling") {
+                    json!([image("sha256:b", vec!["<none>:<none>"])])
+                } else {
+                    json!([
+                        image("sha256:a", vec!["nginx:latest"]),
+                        image("sha256:b", vec!["<none>:<none>"])
+                    ])
+                }
+                .to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\n\
+                     Content-Type: application/json\r\n\
+                     Content-Length: {}\r\n\
+                     Connection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        (host, targets)
+    }
+
+    #[tokio::test]
+    async fn images_are_listed_and_filtered() {
+        let (host, targets) = fake_image_daemon().await;
+        let mut state = test_state().await;
+        state.config.docker_host = Some(host);
+        state.docker = DockerService::new(&state.config).await.unwrap();
+
+        let (_, Json(all)) = list_images(State(state.clone()), Query(ListImagesQuery::default()))
+            .await
+            .unwrap();
+        let tags: Vec<Vec<String>> = all
+            .items
+            .iter()
+            .map(|image| image.repo_tags.clone())
+            .collect();
+        assert_eq!(tags, vec![vec!["nginx:latest".to_string()], vec![]]);
+
+        let query = ListImagesQuery {
+            dangling: Some(true),
+        };
+        let (_, Json(dangling)) = list_images(State(state.clone()), Query(query))
+            .await
+            .unwrap();
+        assert_eq!(dangling.items.len(), 1);
+        assert_eq!(dangling.items[0].id, "sha256:b");
+        assert!(dangling.items[0].repo_tags.is_empty());
+
+        let targets = targets.lock().unwrap();
+        let listed: Vec<&String> = targets
+            .iter()
+            .filter(|target| target.contains("/images/json"))
+            .collect();
+        assert_eq!(listed.len(), 2);
+        assert!(!listed[0].contains("dangling"));
+        assert!(listed[1].contains("dangling"));
+    }
+
+    #[tokio::test]
+    async fn stale_processor_is_not_ready() {
+        let mut state = test_state().await;
+        let interval = Duration::from_secs(state.config.processor_interval_secs);
+        let (readiness, receiver) = watch::channel(Readiness {
+            initialized: true,
+            docker_version: Some("fake".to_string()),
+            last_tick: Some(tokio::time::Instant::now()),
+            ..Default::default()
+        });
+        state.readiness = receiver;
+
+        let (status, _) = readiness_check(State(state.clone())).await;
+        assert_eq!(status, StatusCode::OK);
+
+        readiness.send_modify(|readiness| {
+            readiness.last_tick = Some(tokio::time::Instant::now() - interval * 4)
+        });
+        let (status, Json(body)) = readiness_check(State(state)).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["checks"]["processor"]["status"], "stale");
+    }
+}
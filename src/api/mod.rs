@@ -1,2 +1,5 @@
+pub mod docs;
+pub mod error;
+pub mod extract;
 pub mod handlers;
 pub mod routes;
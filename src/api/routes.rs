@@ -1,25 +1,173 @@
 use axum::{
-    routing::{delete, get, post},
+    body::Body,
+    http::{HeaderName, HeaderValue, Method, Request},
+    response::IntoResponse,
+    routing::{delete, get, patch, post},
     Router,
 };
+use governor::middleware::NoOpMiddleware;
+use std::sync::Arc;
+use std::time::Duration;
+use tower::ServiceBuilder;
+use tower_governor::{
+    governor::GovernorConfigBuilder, key_extractor::PeerIpKeyExtractor, GovernorError,
+    GovernorLayer,
+};
 use tower_http::cors::CorsLayer;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::trace::TraceLayer;
+use tracing::{info, info_span, warn};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::api::docs::ApiDoc;
+use crate::api::error::ApiError;
+use crate::config::Config;
 
 use crate::api::handlers::{
-    create_container, delete_container, get_container, health_check, list_containers, AppState,
+    cluster_stats, container_action, create_container, create_containers_batch, create_network,
+    delete_container, delete_network, exec_container, get_aggregate_logs, get_container,
+    get_container_events, get_container_logs, get_container_stats, get_container_top, health_check,
+    inspect_container, list_containers, list_images, list_networks, list_processors, metrics,
+    prune_containers, pull_image, readiness_check, reconcile_container, update_container, version,
+    watch_container, AppState,
 };
 
+const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
 pub fn create_router(state: AppState) -> Router {
-    let cors = CorsLayer::permissive();
+    let cors = cors_layer(&state.config);
+
+    // Keeps a client supplied X-Request-Id, generates one otherwise, and tags every log
+    // line written while handling the request with it
+    let request_id = ServiceBuilder::new()
+        .layer(SetRequestIdLayer::new(REQUEST_ID_HEADER, MakeRequestUuid))
+        .layer(
+            TraceLayer::new_for_http().make_span_with(|request: &Request<Body>| {
+                let request_id = request
+                    .headers()
+                    .get(REQUEST_ID_HEADER)
+                    .and_then(|value| value.to_str().ok())
+                    .unwrap_or_default();
+                info_span!(
+                    "request",
+                    request_id,
+                    method = %request.method(),
+                    uri = %request.uri(),
+                )
+            }),
+        )
+        .layer(PropagateRequestIdLayer::new(REQUEST_ID_HEADER));
+
+    let create_route = match create_rate_limit(state.config.create_rate_limit_per_minute) {
+        Some(layer) => post(create_container).layer(layer),
+        None => post(create_container),
+    };
 
     let v1_routes = Router::new()
         .route("/health", get(health_check))
+        .route("/ready", get(readiness_check))
+        .route("/version", get(version))
         .route("/containers", get(list_containers))
-        .route("/containers", post(create_container))
+        .route("/containers", create_route)
+        .route("/containers", delete(prune_containers))
+        .route("/containers/batch", post(create_containers_batch))
         .route("/containers/:id", get(get_container))
-        .route("/containers/:id", delete(delete_container));
+        .route("/containers/:id", patch(update_container))
+        .route("/containers/:id", delete(delete_container))
+        .route("/containers/:id/actions", post(container_action))
+        .route("/containers/:id/reconcile", post(reconcile_container))
+        .route("/containers/:id/logs", get(get_container_logs))
+        .route("/containers/:id/stats", get(get_container_stats))
+        .route("/containers/:id/top", get(get_container_top))
+        .route("/containers/:id/exec", post(exec_container))
+        .route("/containers/:id/inspect", get(inspect_container))
+        .route("/containers/:id/events", get(get_container_events))
+        .route("/containers/:id/watch", get(watch_container))
+        .route("/stats", get(cluster_stats))
+        .route("/logs", get(get_aggregate_logs))
+        .route("/images", get(list_images))
+        .route("/images/pull", post(pull_image))
+        .route("/networks", get(list_networks))
+        .route("/networks", post(create_network))
+        .route("/networks/:id", delete(delete_network))
+        .route("/processors", get(list_processors));
 
     Router::new()
+        .route("/metrics", get(metrics))
         .nest("/v1", v1_routes)
+        .merge(SwaggerUi::new("/v1/docs").url("/v1/openapi.json", ApiDoc::openapi()))
+        .layer(request_id)
         .layer(cors)
         .with_state(state)
 }
+
+// Entries were validated when the config was loaded
+fn cors_layer(config: &Config) -> CorsLayer {
+    if config.cors_permissive {
+        warn!("CORS is permissive, any website can call the API from a browser");
+        return CorsLayer::permissive();
+    }
+
+    let origins: Vec<HeaderValue> = config
+        .cors_allowed_origins
+        .iter()
+        .filter_map(|origin| origin.parse().ok())
+        .collect();
+    let methods: Vec<Method> = config
+        .cors_allowed_methods
+        .iter()
+        .filter_map(|method| method.parse().ok())
+        .collect();
+    let headers: Vec<HeaderName> = config
+        .cors_allowed_headers
+        .iter()
+        .filter_map(|header| header.parse().ok())
+        .collect();
+
+    match origins.is_empty() {
+        true => info!("CORS allows no origins, browsers can't call the API cross-origin"),
+        false => info!(
+            "CORS allows origins: {}",
+            config.cors_allowed_origins.join(", ")
+        ),
+    }
+    CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods(methods)
+        .allow_headers(headers)
+}
+
+// Per client IP, the quota refills evenly over the minute
+fn create_rate_limit(per_minute: u32) -> Option<GovernorLayer<PeerIpKeyExtractor, NoOpMiddleware>> {
+    if per_minute == 0 {
+        info!("Create rate limit disabled");
+        return None;
+    }
+
+    let config = GovernorConfigBuilder::default()
+        .period(Duration::from_secs(60) / per_minute)
+        .burst_size(per_minute)
+        .error_handler(|error| match error {
+            GovernorError::TooManyRequests { wait_time, .. } => {
+                ApiError::RateLimited(wait_time.max(1)).into_response()
+            }
+            _ => ApiError::InvalidRequest("Unable to determine the client address".to_string())
+                .into_response(),
+        })
+        .finish()?;
+    let config = Arc::new(config);
+
+    // The limiter keeps a bucket per address, forget the ones that have refilled
+    let limiter = config.limiter().clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            limiter.retain_recent();
+        }
+    });
+
+    info!("Create rate limit: {} per minute and client IP", per_minute);
+    Some(GovernorLayer { config })
+}
@@ -1,4 +1,12 @@
+use anyhow::{anyhow, bail, Context, Result};
+use axum::http::{HeaderName, Method, Uri};
+use serde::Deserialize;
+use std::collections::HashMap;
 use std::env;
+use std::fmt::{self, Display};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
 
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -8,24 +16,432 @@ pub struct Config {
     pub log_level: String,
     pub log_json: bool,
     pub database_url: String,
+    pub db_max_connections: u32,
+    pub db_min_connections: u32,
+    pub db_connect_timeout_secs: u64,
+    /// How long a SQLite write waits for a lock held by another connection
+    pub sqlite_busy_timeout_ms: u64,
+    pub max_memory_mb: Option<u64>,
+    pub processor_interval_secs: u64,
+    pub max_restart_retries: u32,
+    /// Default grace period for stops, containers can override it with stop_timeout_secs
+    pub stop_timeout_secs: u64,
+    /// How long the processor keeps finishing Removing containers after shutdown, 0 skips it
+    pub shutdown_drain_timeout_secs: u64,
+    /// Containers still Pending, Pulling, Created or Restarting after this long are
+    /// failed, 0 turns it off
+    pub stuck_timeout_secs: u64,
+    pub processor_concurrency: usize,
+    /// Seconds without a heartbeat after which a processor counts as gone and other
+    /// processors sharing the database may take over its containers
+    pub owner_lease_secs: u64,
+    /// Attempts for Docker create, start and stop before transient errors count as failures
+    pub docker_retry_attempts: u32,
+    /// Delay before the first retry, doubled after every further attempt
+    pub docker_retry_base_ms: u64,
+    /// Take over an existing Docker container with the requested name and image instead of failing
+    pub adopt_existing_containers: bool,
+    /// How long an Idempotency-Key keeps replaying the original create response
+    pub idempotency_key_ttl_secs: u64,
+    /// Output above this many bytes is cut off when running exec
+    pub exec_max_output_bytes: usize,
+    /// Hold off serving HTTP until the first successful Docker check
+    pub wait_for_docker: bool,
+    /// Image pulls taking longer than this fail the container
+    pub pull_timeout_secs: u64,
+    /// Bounds every other Docker API call, stops get their grace period on top
+    pub docker_call_timeout_secs: u64,
+    /// Creates allowed per client IP and minute, 0 turns the limit off
+    pub create_rate_limit_per_minute: u32,
+    /// Cap on containers that are not Stopped, Failed or removed, unlimited when unset
+    pub max_containers: Option<u64>,
+    /// Directory running containers' logs are copied to, one file per container, so they
+    /// stay readable after removal. Off when unset
+    pub container_log_dir: Option<String>,
+    /// Size an archived log file may reach before it is rotated
+    pub container_log_max_bytes: u64,
+    /// Lets any origin call the API with any method and header, for local development only
+    pub cors_permissive: bool,
+    /// Origins browsers may call the API from, e.g. "https://dashboard.example.com". Other
+    /// origins are refused, which with none listed means all of them
+    pub cors_allowed_origins: Vec<String>,
+    pub cors_allowed_methods: Vec<String>,
+    pub cors_allowed_headers: Vec<String>,
+    /// tcp:// or https:// address of a remote Docker daemon, local socket when unset
+    pub docker_host: Option<String>,
+    /// Directory containing key.pem, cert.pem and ca.pem for TLS connections
+    pub docker_tls_cert_path: Option<String>,
+    /// Credentials for pulling private images keyed by registry host, e.g. "ghcr.io",
+    /// Docker Hub images use "docker.io"
+    pub registries: HashMap<String, RegistryCredentials>,
+}
+
+/// Either a username and password or a registry token
+#[derive(Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RegistryCredentials {
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub token: Option<String>,
+}
+
+// Config is logged at startup, so secrets never make it into Debug output
+impl fmt::Debug for RegistryCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let redacted = |secret: &Option<String>| secret.as_ref().map(|_| "<redacted>");
+        f.debug_struct("RegistryCredentials")
+            .field("username", &self.username)
+            .field("password", &redacted(&self.password))
+            .field("token", &redacted(&self.token))
+            .finish()
+    }
+}
+
+/// Settings read from a TOML config file, keys are the Config field names. Anything
+/// left out falls back to the environment variable defaults.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileConfig {
+    server_port: Option<u16>,
+    server_host: Option<String>,
+    processor_name: Option<String>,
+    log_level: Option<String>,
+    log_json: Option<bool>,
+    database_url: Option<String>,
+    db_max_connections: Option<u32>,
+    db_min_connections: Option<u32>,
+    db_connect_timeout_secs: Option<u64>,
+    sqlite_busy_timeout_ms: Option<u64>,
+    max_memory_mb: Option<u64>,
+    processor_interval_secs: Option<u64>,
+    max_restart_retries: Option<u32>,
+    stop_timeout_secs: Option<u64>,
+    shutdown_drain_timeout_secs: Option<u64>,
+    stuck_timeout_secs: Option<u64>,
+    processor_concurrency: Option<usize>,
+    owner_lease_secs: Option<u64>,
+    docker_retry_attempts: Option<u32>,
+    docker_retry_base_ms: Option<u64>,
+    adopt_existing_containers: Option<bool>,
+    idempotency_key_ttl_secs: Option<u64>,
+    exec_max_output_bytes: Option<usize>,
+    wait_for_docker: Option<bool>,
+    pull_timeout_secs: Option<u64>,
+    docker_call_timeout_secs: Option<u64>,
+    create_rate_limit_per_minute: Option<u32>,
+    max_containers: Option<u64>,
+    container_log_dir: Option<String>,
+    container_log_max_bytes: Option<u64>,
+    cors_permissive: Option<bool>,
+    cors_allowed_origins: Option<Vec<String>>,
+    cors_allowed_methods: Option<Vec<String>>,
+    cors_allowed_headers: Option<Vec<String>>,
+    docker_host: Option<String>,
+    docker_tls_cert_path: Option<String>,
+    registries: Option<HashMap<String, RegistryCredentials>>,
 }
 
 impl Config {
-    pub fn from_env() -> Self {
-        let database_url = env::var("DATABASE_URL")
-            .unwrap_or_else(|_| "sqlite://./nebulet.db?mode=rwc".to_string());
+    // Malformed values fail startup instead of silently falling back to the default
+    pub fn from_env() -> Result<Self> {
+        Self::load(FileConfig::default())
+    }
+
+    /// Reads settings from a TOML file, environment variables still take precedence
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        let file: FileConfig = toml::from_str(&contents)
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+        Self::load(file)
+    }
 
+    /// Fixed settings for tests, independent of the environment. Uses a single connection
+    /// to an in-memory database and retries Docker calls without waiting
+    #[cfg(test)]
+    pub(crate) fn for_tests() -> Self {
         Self {
-            server_port: env::var("SERVER_PORT")
-                .unwrap_or_else(|_| "8080".to_string())
-                .parse()
-                .unwrap_or(8080),
-            server_host: env::var("SERVER_HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
-            processor_name: env::var("PROCESSOR_NAME")
-                .unwrap_or_else(|_| "nebulet-processor".to_string()),
-            log_level: env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string()),
-            log_json: env::var("LOG_JSON").is_ok(),
-            database_url,
+            server_port: 8080,
+            server_host: "127.0.0.1".to_string(),
+            processor_name: "test-processor".to_string(),
+            log_level: "info".to_string(),
+            log_json: false,
+            // Every connection to sqlite::memory: opens its own database, so keep to one
+            database_url: "sqlite::memory:".to_string(),
+            db_max_connections: 1,
+            db_min_connections: 1,
+            db_connect_timeout_secs: 8,
+            sqlite_busy_timeout_ms: 5000,
+            max_memory_mb: None,
+            processor_interval_secs: 1,
+            max_restart_retries: 3,
+            stop_timeout_secs: 30,
+            shutdown_drain_timeout_secs: 30,
+            stuck_timeout_secs: 3600,
+            processor_concurrency: 4,
+            idempotency_key_ttl_secs: 86400,
+            exec_max_output_bytes: 1048576,
+            owner_lease_secs: 30,
+            docker_retry_attempts: 3,
+            docker_retry_base_ms: 0,
+            wait_for_docker: false,
+            adopt_existing_containers: true,
+            pull_timeout_secs: 600,
+            docker_call_timeout_secs: 60,
+            create_rate_limit_per_minute: 60,
+            max_containers: None,
+            container_log_dir: None,
+            container_log_max_bytes: 10485760,
+            cors_permissive: false,
+            cors_allowed_origins: Vec::new(),
+            cors_allowed_methods: Vec::new(),
+            cors_allowed_headers: Vec::new(),
+            docker_host: None,
+            docker_tls_cert_path: None,
+            registries: HashMap::new(),
+        }
+    }
+
+    fn load(file: FileConfig) -> Result<Self> {
+        let config = Self {
+            server_port: parse_var("SERVER_PORT", file.server_port, 8080)?,
+            server_host: string_var("SERVER_HOST", file.server_host, "0.0.0.0"),
+            processor_name: string_var("PROCESSOR_NAME", file.processor_name, "nebulet-processor"),
+            log_level: string_var("LOG_LEVEL", file.log_level, "info"),
+            // Any LOG_JSON value turns JSON logs on, as it always has
+            log_json: env::var("LOG_JSON").is_ok() || file.log_json.unwrap_or(false),
+            database_url: string_var(
+                "DATABASE_URL",
+                file.database_url,
+                "sqlite://./nebulet.db?mode=rwc",
+            ),
+            db_max_connections: parse_var("DB_MAX_CONNECTIONS", file.db_max_connections, 10)?,
+            db_min_connections: parse_var("DB_MIN_CONNECTIONS", file.db_min_connections, 1)?,
+            db_connect_timeout_secs: parse_var(
+                "DB_CONNECT_TIMEOUT_SECS",
+                file.db_connect_timeout_secs,
+                8,
+            )?,
+            sqlite_busy_timeout_ms: parse_var(
+                "SQLITE_BUSY_TIMEOUT_MS",
+                file.sqlite_busy_timeout_ms,
+                5000,
+            )?,
+            max_memory_mb: parse_optional_var("MAX_MEMORY_MB")?.or(file.max_memory_mb),
+            processor_interval_secs: parse_var(
+                "PROCESSOR_INTERVAL_SECS",
+                file.processor_interval_secs,
+                10,
+            )?,
+            max_restart_retries: parse_var("MAX_RESTART_RETRIES", file.max_restart_retries, 3)?,
+            stop_timeout_secs: parse_var("STOP_TIMEOUT_SECS", file.stop_timeout_secs, 30)?,
+            shutdown_drain_timeout_secs: parse_var(
+                "SHUTDOWN_DRAIN_TIMEOUT_SECS",
+                file.shutdown_drain_timeout_secs,
+                30,
+            )?,
+            stuck_timeout_secs: parse_var("STUCK_TIMEOUT_SECS", file.stuck_timeout_secs, 3600)?,
+            processor_concurrency: parse_var(
+                "PROCESSOR_CONCURRENCY",
+                file.processor_concurrency,
+                4,
+            )?,
+            idempotency_key_ttl_secs: parse_var(
+                "IDEMPOTENCY_KEY_TTL_SECS",
+                file.idempotency_key_ttl_secs,
+                86400,
+            )?,
+            exec_max_output_bytes: parse_var(
+                "EXEC_MAX_OUTPUT_BYTES",
+                file.exec_max_output_bytes,
+                1048576,
+            )?,
+            owner_lease_secs: parse_var("OWNER_LEASE_SECS", file.owner_lease_secs, 30)?,
+            docker_retry_attempts: parse_var(
+                "DOCKER_RETRY_ATTEMPTS",
+                file.docker_retry_attempts,
+                3,
+            )?,
+            docker_retry_base_ms: parse_var(
+                "DOCKER_RETRY_BASE_MS",
+                file.docker_retry_base_ms,
+                500,
+            )?,
+            wait_for_docker: parse_flag("WAIT_FOR_DOCKER", file.wait_for_docker, false)?,
+            adopt_existing_containers: parse_flag(
+                "ADOPT_EXISTING_CONTAINERS",
+                file.adopt_existing_containers,
+                true,
+            )?,
+            pull_timeout_secs: parse_var("PULL_TIMEOUT_SECS", file.pull_timeout_secs, 600)?,
+            docker_call_timeout_secs: parse_var(
+                "DOCKER_CALL_TIMEOUT_SECS",
+                file.docker_call_timeout_secs,
+                60,
+            )?,
+            create_rate_limit_per_minute: parse_var(
+                "CREATE_RATE_LIMIT_PER_MINUTE",
+                file.create_rate_limit_per_minute,
+                60,
+            )?,
+            max_containers: parse_optional_var("MAX_CONTAINERS")?.or(file.max_containers),
+            container_log_dir: env::var("CONTAINER_LOG_DIR")
+                .ok()
+                .or(file.container_log_dir),
+            container_log_max_bytes: parse_var(
+                "CONTAINER_LOG_MAX_BYTES",
+                file.container_log_max_bytes,
+                10485760,
+            )?,
+            cors_permissive: parse_flag("CORS_PERMISSIVE", file.cors_permissive, false)?,
+            cors_allowed_origins: list_var("CORS_ALLOWED_ORIGINS", file.cors_allowed_origins, &[]),
+            cors_allowed_methods: list_var(
+                "CORS_ALLOWED_METHODS",
+                file.cors_allowed_methods,
+                &["GET", "POST", "PATCH", "DELETE"],
+            ),
+            cors_allowed_headers: list_var(
+                "CORS_ALLOWED_HEADERS",
+                file.cors_allowed_headers,
+                &["content-type", "idempotency-key", "x-request-id"],
+            ),
+            docker_host: env::var("DOCKER_HOST").ok().or(file.docker_host),
+            docker_tls_cert_path: env::var("DOCKER_CERT_PATH")
+                .ok()
+                .or(file.docker_tls_cert_path),
+            registries: registries_var(file.registries.unwrap_or_default())?,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.server_port == 0 {
+            bail!("Invalid SERVER_PORT '0': must be between 1 and 65535");
+        }
+        tracing::Level::from_str(&self.log_level).map_err(|_| {
+            anyhow!(
+                "Invalid LOG_LEVEL '{}': expected trace, debug, info, warn or error",
+                self.log_level
+            )
+        })?;
+        if self.database_url.trim().is_empty() {
+            bail!("DATABASE_URL must not be empty");
+        }
+        for origin in &self.cors_allowed_origins {
+            validate_origin(origin)?;
+        }
+        for method in &self.cors_allowed_methods {
+            Method::from_str(method)
+                .map_err(|_| anyhow!("Invalid CORS_ALLOWED_METHODS entry '{}'", method))?;
+        }
+        for header in &self.cors_allowed_headers {
+            HeaderName::from_str(header)
+                .map_err(|_| anyhow!("Invalid CORS_ALLOWED_HEADERS entry '{}'", header))?;
+        }
+        for (host, credentials) in &self.registries {
+            let has_login = credentials.username.is_some() && credentials.password.is_some();
+            if has_login == credentials.token.is_some() {
+                bail!(
+                    "Invalid credentials for registry {}: set either username and password or token",
+                    host
+                );
+            }
         }
+        Ok(())
+    }
+}
+
+fn string_var(name: &str, file: Option<String>, default: &str) -> String {
+    env::var(name)
+        .ok()
+        .or(file)
+        .unwrap_or_else(|| default.to_string())
+}
+
+fn parse_var<T>(name: &str, file: Option<T>, default: T) -> Result<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    Ok(parse_optional_var(name)?.or(file).unwrap_or(default))
+}
+
+fn parse_optional_var<T>(name: &str) -> Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let Ok(value) = env::var(name) else {
+        return Ok(None);
+    };
+    value
+        .parse()
+        .map(Some)
+        .map_err(|e| anyhow!("Invalid {} '{}': {}", name, value, e))
+}
+
+// REGISTRY_AUTH holds a JSON object like {"ghcr.io": {"token": "..."}}, its entries
+// replace file entries for the same host. The value is never echoed in errors.
+fn registries_var(
+    mut registries: HashMap<String, RegistryCredentials>,
+) -> Result<HashMap<String, RegistryCredentials>> {
+    if let Ok(value) = env::var("REGISTRY_AUTH") {
+        let overrides: HashMap<String, RegistryCredentials> = serde_json::from_str(&value)
+            .map_err(|e| {
+                anyhow!(
+                    "Invalid REGISTRY_AUTH at line {} column {}: expected a JSON object of registry credentials",
+                    e.line(),
+                    e.column()
+                )
+            })?;
+        registries.extend(overrides);
+    }
+    Ok(registries)
+}
+
+// Comma separated in the environment, a list in the config file
+fn list_var(name: &str, file: Option<Vec<String>>, default: &[&str]) -> Vec<String> {
+    match env::var(name) {
+        Ok(value) => value
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(str::to_string)
+            .collect(),
+        Err(_) => file.unwrap_or_else(|| default.iter().map(|entry| entry.to_string()).collect()),
+    }
+}
+
+// Browsers send the Origin header as scheme://host[:port], anything else never matches
+fn validate_origin(origin: &str) -> Result<()> {
+    let invalid = || {
+        anyhow!(
+            "Invalid CORS_ALLOWED_ORIGINS entry '{}': expected http(s)://host[:port] without a path",
+            origin
+        )
+    };
+    let uri = Uri::from_str(origin).map_err(|_| invalid())?;
+    // Rebuilding it from its parts catches paths, trailing slashes and credentials
+    let well_formed = match (uri.scheme_str(), uri.authority()) {
+        (Some(scheme @ ("http" | "https")), Some(authority)) => {
+            !authority.host().is_empty()
+                && !authority.as_str().contains('@')
+                && origin == format!("{}://{}", scheme, authority)
+        }
+        _ => false,
+    };
+    match well_formed {
+        true => Ok(()),
+        false => Err(invalid()),
+    }
+}
+
+fn parse_flag(name: &str, file: Option<bool>, default: bool) -> Result<bool> {
+    match env::var(name).as_deref() {
+        Err(_) => Ok(file.unwrap_or(default)),
+        Ok("true" | "1") => Ok(true),
+        Ok("false" | "0") => Ok(false),
+        Ok(value) => bail!("Invalid {} '{}': expected true, false, 1 or 0", name, value),
     }
 }
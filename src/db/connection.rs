@@ -1,5 +1,9 @@
 use anyhow::Result;
-use sea_orm::{Database, DatabaseConnection};
+use sea_orm::{ConnectOptions, Database, DatabaseConnection, SqlxSqliteConnector};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+use sqlx::ConnectOptions as _;
+use std::str::FromStr;
+use std::time::Duration;
 use tracing::info;
 
 use crate::config::Config;
@@ -7,8 +11,47 @@ use crate::config::Config;
 pub async fn establish_connection(config: &Config) -> Result<DatabaseConnection> {
     info!("Connecting to database: {}", config.database_url);
 
-    let db = Database::connect(&config.database_url).await?;
+    info!(
+        "Database pool settings: max_connections={}, min_connections={}, connect_timeout={}s",
+        config.db_max_connections, config.db_min_connections, config.db_connect_timeout_secs
+    );
+
+    let db = match config.database_url.starts_with("sqlite:") {
+        true => connect_sqlite(config).await?,
+        false => {
+            let mut options = ConnectOptions::new(config.database_url.clone());
+            options
+                .max_connections(config.db_max_connections)
+                .min_connections(config.db_min_connections)
+                .connect_timeout(Duration::from_secs(config.db_connect_timeout_secs));
+            Database::connect(options).await?
+        }
+    };
 
     info!("Database connection established successfully");
     Ok(db)
 }
+
+// The processor and API write concurrently, WAL lets readers proceed during a write and
+// the busy timeout makes writers wait for the lock instead of failing with "database is
+// locked". Both are set on the connect options so every pooled connection gets them.
+async fn connect_sqlite(config: &Config) -> Result<DatabaseConnection> {
+    let options = SqliteConnectOptions::from_str(&config.database_url)?
+        .journal_mode(SqliteJournalMode::Wal)
+        .busy_timeout(Duration::from_millis(config.sqlite_busy_timeout_ms))
+        // Same statement logging sea-orm enables for its own pools
+        .log_statements(log::LevelFilter::Info);
+
+    info!(
+        "SQLite settings: journal_mode=WAL, busy_timeout={}ms",
+        config.sqlite_busy_timeout_ms
+    );
+
+    let pool = SqlitePoolOptions::new()
+        .max_connections(config.db_max_connections)
+        .min_connections(config.db_min_connections)
+        .acquire_timeout(Duration::from_secs(config.db_connect_timeout_secs))
+        .connect_with(options)
+        .await?;
+    Ok(SqlxSqliteConnector::from_sqlx_sqlite_pool(pool))
+}
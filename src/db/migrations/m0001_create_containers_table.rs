@@ -0,0 +1,39 @@
+use sea_orm_migration::prelude::*;
+
+use super::Containers;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Timestamps are stored as RFC3339 text to match the model
+        manager
+            .create_table(
+                Table::create()
+                    .table(Containers::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Containers::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Containers::Name).string().not_null())
+                    .col(ColumnDef::new(Containers::Image).string().not_null())
+                    .col(ColumnDef::new(Containers::Status).string().not_null())
+                    .col(ColumnDef::new(Containers::DockerId).string())
+                    .col(ColumnDef::new(Containers::CreatedAt).text().not_null())
+                    .col(ColumnDef::new(Containers::UpdatedAt).text().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Containers::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,18 @@
+use sea_orm_migration::prelude::*;
+
+use super::{add_column_if_missing, drop_column, Containers};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // JSON encoded Vec<String>
+        add_column_if_missing(manager, ColumnDef::new(Containers::Env).text().to_owned()).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        drop_column(manager, Containers::Env).await
+    }
+}
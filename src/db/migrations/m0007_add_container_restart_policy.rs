@@ -0,0 +1,33 @@
+use sea_orm_migration::prelude::*;
+
+use super::{add_column_if_missing, drop_column, Containers};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        add_column_if_missing(
+            manager,
+            ColumnDef::new(Containers::RestartPolicy)
+                .string()
+                .to_owned(),
+        )
+        .await?;
+        add_column_if_missing(
+            manager,
+            ColumnDef::new(Containers::RestartCount)
+                .big_integer()
+                .not_null()
+                .default(0)
+                .to_owned(),
+        )
+        .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        drop_column(manager, Containers::RestartPolicy).await?;
+        drop_column(manager, Containers::RestartCount).await
+    }
+}
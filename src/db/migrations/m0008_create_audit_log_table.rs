@@ -0,0 +1,49 @@
+use sea_orm_migration::prelude::*;
+
+use super::AuditLog;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // No foreign key so the history outlives the container row
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuditLog::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AuditLog::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AuditLog::ContainerId).string().not_null())
+                    .col(ColumnDef::new(AuditLog::FromStatus).string())
+                    .col(ColumnDef::new(AuditLog::ToStatus).string().not_null())
+                    .col(ColumnDef::new(AuditLog::Timestamp).text().not_null())
+                    .col(ColumnDef::new(AuditLog::Reason).text())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_audit_log_container_id")
+                    .table(AuditLog::Table)
+                    .col(AuditLog::ContainerId)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuditLog::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,92 @@
+use chrono::{DateTime, Utc};
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DbBackend};
+use tracing::warn;
+
+use super::Containers;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        normalize_timestamps(manager).await?;
+
+        // SQLite can't change a column type, its rows are rewritten in the format sqlx decodes
+        if manager.get_database_backend() == DbBackend::Postgres {
+            for column in ["created_at", "updated_at"] {
+                manager
+                    .get_connection()
+                    .execute_unprepared(&format!(
+                        "ALTER TABLE containers ALTER COLUMN {0} TYPE TIMESTAMPTZ USING {0}::timestamptz",
+                        column
+                    ))
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() == DbBackend::Postgres {
+            for column in ["created_at", "updated_at"] {
+                manager
+                    .get_connection()
+                    .execute_unprepared(&format!(
+                        "ALTER TABLE containers ALTER COLUMN {0} TYPE TEXT USING to_char({0} AT TIME ZONE 'UTC', 'YYYY-MM-DD\"T\"HH24:MI:SS.US\"+00:00\"')",
+                        column
+                    ))
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Parses every stored RFC3339 string and writes it back as a UTC timestamp
+async fn normalize_timestamps(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    let db = manager.get_connection();
+    let backend = manager.get_database_backend();
+
+    let select = Query::select()
+        .columns([Containers::Id, Containers::CreatedAt, Containers::UpdatedAt])
+        .from(Containers::Table)
+        .to_owned();
+
+    for row in db.query_all(backend.build(&select)).await? {
+        let id: String = row.try_get("", "id")?;
+        let created_at = parse_timestamp(&id, &row.try_get::<String>("", "created_at")?);
+        let updated_at = parse_timestamp(&id, &row.try_get::<String>("", "updated_at")?);
+
+        // Postgres still has text columns at this point, the ALTER casts them afterwards
+        let (created_at, updated_at): (Value, Value) = match backend {
+            DbBackend::Postgres => (
+                created_at.to_rfc3339().into(),
+                updated_at.to_rfc3339().into(),
+            ),
+            _ => (created_at.into(), updated_at.into()),
+        };
+
+        let update = Query::update()
+            .table(Containers::Table)
+            .value(Containers::CreatedAt, created_at)
+            .value(Containers::UpdatedAt, updated_at)
+            .and_where(Expr::col(Containers::Id).eq(id))
+            .to_owned();
+        db.execute(backend.build(&update)).await?;
+    }
+    Ok(())
+}
+
+fn parse_timestamp(container_id: &str, value: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .unwrap_or_else(|e| {
+            warn!(
+                "Container {} has unparseable timestamp '{}' ({}), using the current time",
+                container_id, value, e
+            );
+            Utc::now()
+        })
+}
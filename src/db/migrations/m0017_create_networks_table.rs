@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+
+use super::Networks;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Networks::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Networks::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Networks::Name)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Networks::Driver).string())
+                    .col(ColumnDef::new(Networks::DockerId).string().not_null())
+                    .col(
+                        ColumnDef::new(Networks::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Networks::Table).to_owned())
+            .await
+    }
+}
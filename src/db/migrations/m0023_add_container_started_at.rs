@@ -0,0 +1,31 @@
+use sea_orm_migration::prelude::*;
+
+use super::{add_column_if_missing, drop_column, Containers};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        add_column_if_missing(
+            manager,
+            ColumnDef::new(Containers::StartedAt)
+                .timestamp_with_time_zone()
+                .to_owned(),
+        )
+        .await?;
+        add_column_if_missing(
+            manager,
+            ColumnDef::new(Containers::FinishedAt)
+                .timestamp_with_time_zone()
+                .to_owned(),
+        )
+        .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        drop_column(manager, Containers::FinishedAt).await?;
+        drop_column(manager, Containers::StartedAt).await
+    }
+}
@@ -0,0 +1,37 @@
+use sea_orm_migration::prelude::*;
+
+use super::{add_column_if_missing, drop_column, Containers};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        add_column_if_missing(
+            manager,
+            ColumnDef::new(Containers::StatusChangedAt)
+                .timestamp_with_time_zone()
+                .to_owned(),
+        )
+        .await?;
+
+        // The last write is the best guess for rows that changed status before the column
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(Containers::Table)
+                    .value(
+                        Containers::StatusChangedAt,
+                        Expr::col(Containers::UpdatedAt),
+                    )
+                    .and_where(Expr::col(Containers::StatusChangedAt).is_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        drop_column(manager, Containers::StatusChangedAt).await
+    }
+}
@@ -0,0 +1,254 @@
+use anyhow::Result;
+use sea_orm::DatabaseConnection;
+use sea_orm_migration::prelude::*;
+use tracing::info;
+
+mod m0001_create_containers_table;
+mod m0002_add_container_env;
+mod m0003_add_container_ports;
+mod m0004_add_container_volumes;
+mod m0005_add_container_resource_limits;
+mod m0006_add_container_error_message;
+mod m0007_add_container_restart_policy;
+mod m0008_create_audit_log_table;
+mod m0009_add_container_command;
+mod m0010_add_container_labels;
+mod m0011_add_container_stop_timeout;
+mod m0012_convert_container_timestamps;
+mod m0013_create_idempotency_keys_table;
+mod m0014_add_container_version;
+mod m0015_add_container_docker_attempts;
+mod m0016_add_container_network;
+mod m0017_create_networks_table;
+mod m0018_add_container_exit_code;
+mod m0019_add_container_owner;
+mod m0020_create_processor_heartbeats_table;
+mod m0021_add_container_deleted_at;
+mod m0022_add_container_image_digest;
+mod m0023_add_container_started_at;
+mod m0024_add_container_health;
+mod m0025_add_container_healthcheck;
+mod m0026_add_container_docker_created_at;
+mod m0027_add_container_auto_remove;
+mod m0028_add_container_status_changed_at;
+mod m0029_add_container_spec_hash;
+mod m0030_add_container_entrypoint;
+
+pub struct Migrator;
+
+#[async_trait::async_trait]
+impl MigratorTrait for Migrator {
+    fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+        vec![
+            Box::new(m0001_create_containers_table::Migration),
+            Box::new(m0002_add_container_env::Migration),
+            Box::new(m0003_add_container_ports::Migration),
+            Box::new(m0004_add_container_volumes::Migration),
+            Box::new(m0005_add_container_resource_limits::Migration),
+            Box::new(m0006_add_container_error_message::Migration),
+            Box::new(m0007_add_container_restart_policy::Migration),
+            Box::new(m0008_create_audit_log_table::Migration),
+            Box::new(m0009_add_container_command::Migration),
+            Box::new(m0010_add_container_labels::Migration),
+            Box::new(m0011_add_container_stop_timeout::Migration),
+            Box::new(m0012_convert_container_timestamps::Migration),
+            Box::new(m0013_create_idempotency_keys_table::Migration),
+            Box::new(m0014_add_container_version::Migration),
+            Box::new(m0015_add_container_docker_attempts::Migration),
+            Box::new(m0016_add_container_network::Migration),
+            Box::new(m0017_create_networks_table::Migration),
+            Box::new(m0018_add_container_exit_code::Migration),
+            Box::new(m0019_add_container_owner::Migration),
+            Box::new(m0020_create_processor_heartbeats_table::Migration),
+            Box::new(m0021_add_container_deleted_at::Migration),
+            Box::new(m0022_add_container_image_digest::Migration),
+            Box::new(m0023_add_container_started_at::Migration),
+            Box::new(m0024_add_container_health::Migration),
+            Box::new(m0025_add_container_healthcheck::Migration),
+            Box::new(m0026_add_container_docker_created_at::Migration),
+            Box::new(m0027_add_container_auto_remove::Migration),
+            Box::new(m0028_add_container_status_changed_at::Migration),
+            Box::new(m0029_add_container_spec_hash::Migration),
+            Box::new(m0030_add_container_entrypoint::Migration),
+        ]
+    }
+}
+
+pub async fn run_migrations(db: &DatabaseConnection) -> Result<()> {
+    info!(
+        "Running database migrations for {:?}...",
+        db.get_database_backend()
+    );
+
+    Migrator::up(db, None).await?;
+
+    info!("Database migrations completed successfully");
+    Ok(())
+}
+
+#[derive(DeriveIden)]
+pub(crate) enum Containers {
+    Table,
+    Id,
+    Name,
+    Image,
+    Status,
+    DockerId,
+    Env,
+    Ports,
+    Volumes,
+    MemoryMb,
+    CpuShares,
+    ErrorMessage,
+    RestartPolicy,
+    RestartCount,
+    Command,
+    Entrypoint,
+    Labels,
+    StopTimeoutSecs,
+    Version,
+    DockerAttempts,
+    Network,
+    ExitCode,
+    Owner,
+    DeletedAt,
+    ImageDigest,
+    StartedAt,
+    FinishedAt,
+    Health,
+    Healthcheck,
+    DockerCreatedAt,
+    AutoRemove,
+    StatusChangedAt,
+    SpecHash,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+pub(crate) enum AuditLog {
+    Table,
+    Id,
+    ContainerId,
+    FromStatus,
+    ToStatus,
+    Timestamp,
+    Reason,
+}
+
+#[derive(DeriveIden)]
+pub(crate) enum IdempotencyKeys {
+    Table,
+    Key,
+    ContainerId,
+    Response,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+pub(crate) enum ProcessorHeartbeats {
+    Table,
+    Name,
+    StartedAt,
+    LastSeen,
+}
+
+#[derive(DeriveIden)]
+pub(crate) enum Networks {
+    Table,
+    Id,
+    Name,
+    Driver,
+    DockerId,
+    CreatedAt,
+}
+
+// Databases created before versioned migrations already have some of these columns,
+// so every column migration checks first instead of failing on a duplicate
+pub(crate) async fn add_column_if_missing(
+    manager: &SchemaManager<'_>,
+    mut definition: ColumnDef,
+) -> Result<(), DbErr> {
+    if manager
+        .has_column(Containers::Table.to_string(), definition.get_column_name())
+        .await?
+    {
+        return Ok(());
+    }
+
+    manager
+        .alter_table(
+            Table::alter()
+                .table(Containers::Table)
+                .add_column(&mut definition)
+                .to_owned(),
+        )
+        .await
+}
+
+pub(crate) async fn drop_column(
+    manager: &SchemaManager<'_>,
+    column: Containers,
+) -> Result<(), DbErr> {
+    manager
+        .alter_table(
+            Table::alter()
+                .table(Containers::Table)
+                .drop_column(column)
+                .to_owned(),
+        )
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{ConnectOptions, Database};
+
+    const TABLES: [&str; 5] = [
+        "containers",
+        "audit_log",
+        "idempotency_keys",
+        "processor_heartbeats",
+        "networks",
+    ];
+
+    // Up, down and up again, so every migration can be rolled back and reapplied
+    async fn check_migrations(url: &str) {
+        let mut options = ConnectOptions::new(url);
+        // Every connection to sqlite::memory: opens its own database, so keep to one
+        options.max_connections(1).sqlx_logging(false);
+        let db = Database::connect(options).await.unwrap();
+        let manager = SchemaManager::new(&db);
+
+        run_migrations(&db).await.unwrap();
+        for table in TABLES {
+            assert!(manager.has_table(table).await.unwrap(), "{} missing", table);
+        }
+        assert!(manager.has_column("containers", "spec_hash").await.unwrap());
+
+        Migrator::down(&db, None).await.unwrap();
+        for table in TABLES {
+            assert!(!manager.has_table(table).await.unwrap(), "{} left", table);
+        }
+
+        run_migrations(&db).await.unwrap();
+        assert!(manager.has_table("containers").await.unwrap());
+        Migrator::down(&db, None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn migrations_run_on_sqlite() {
+        check_migrations("sqlite::memory:").await;
+    }
+
+    // Needs a database to own, e.g. TEST_POSTGRES_URL=postgres://postgres@localhost/nebulet_test
+    #[tokio::test]
+    async fn migrations_run_on_postgres() {
+        let Ok(url) = std::env::var("TEST_POSTGRES_URL") else {
+            eprintln!("TEST_POSTGRES_URL is not set, skipping");
+            return;
+        };
+        check_migrations(&url).await;
+    }
+}
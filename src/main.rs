@@ -1,12 +1,16 @@
 mod api;
 mod config;
 mod db;
+mod metrics;
 mod models;
 mod services;
 
 use anyhow::Result;
+use clap::{Parser, Subcommand};
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::Arc;
 use tokio::signal;
 use tracing::{error, info, Level};
 
@@ -14,11 +18,36 @@ use crate::api::handlers::AppState;
 use crate::api::routes::create_router;
 use crate::config::Config;
 use crate::db::{establish_connection, run_migrations};
-use crate::services::ProcessorService;
+use crate::services::{DockerService, ProcessorService};
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Container management service")]
+struct Cli {
+    /// TOML config file, environment variables override its values
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Run the API server and processor, the default without a subcommand
+    Serve,
+    /// Run database migrations and exit
+    Migrate,
+    /// Run a single reconciliation pass over all containers and exit
+    ReconcileOnce,
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let config = Config::from_env();
+    let cli = Cli::parse();
+    let config = match &cli.config {
+        Some(path) => Config::from_file(path)?,
+        None => Config::from_env()?,
+    };
 
     match config.log_json {
         true => {
@@ -37,6 +66,33 @@ async fn main() -> Result<()> {
         }
     };
 
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => serve(config).await,
+        Command::Migrate => migrate(&config).await,
+        Command::ReconcileOnce => reconcile_once(&config).await,
+    }
+}
+
+async fn migrate(config: &Config) -> Result<()> {
+    let db = establish_connection(config).await?;
+    run_migrations(&db).await?;
+    info!("Migrations finished");
+    Ok(())
+}
+
+// Meant for cron jobs, exits non-zero when Docker or the database can't be reached
+async fn reconcile_once(config: &Config) -> Result<()> {
+    let db = establish_connection(config).await?;
+    run_migrations(&db).await?;
+
+    let docker = DockerService::new(config).await?;
+    let processor = ProcessorService::new(config, db, docker).await?;
+    processor.reconcile_once().await?;
+    info!("Reconciliation pass finished");
+    Ok(())
+}
+
+async fn serve(config: Config) -> Result<()> {
     info!("Starting Nebulet container service...");
     info!("Configuration: {:?}", config);
 
@@ -44,31 +100,69 @@ async fn main() -> Result<()> {
     run_migrations(&db).await?;
     info!("Database initialized successfully");
 
-    let mut processor = ProcessorService::new(config.processor_name.clone(), db.clone()).await?;
+    let metrics = crate::metrics::install_recorder()?;
+
+    let docker = DockerService::new(&config).await?;
+
+    let processor = Arc::new(ProcessorService::new(&config, db.clone(), docker.clone()).await?);
     info!("Processor service initialized successfully");
 
-    let state = AppState { db };
+    let state = AppState {
+        db,
+        docker,
+        config: config.clone(),
+        metrics,
+        readiness: processor.readiness(),
+        status_updates: processor.status_updates(),
+        reconcile_requests: processor.reconcile_requests(),
+        log_archive: processor.log_archive(),
+    };
+
+    // Run api and processor concurrently
+    let processor_task = tokio::spawn({
+        let processor = processor.clone();
+        async move { processor.start().await }
+    });
+
+    if config.wait_for_docker {
+        info!("Waiting for Docker before serving HTTP");
+        let mut readiness = processor.readiness();
+        tokio::select! {
+            _ = readiness.wait_for(|readiness| readiness.docker_version.is_some()) => {}
+            _ = processor.shutdown_requested() => {}
+            _ = shutdown_signal() => processor.shutdown(),
+        }
+    }
 
     let app = create_router(state);
     let addr = format!("{}:{}", config.server_host, config.server_port).parse::<SocketAddr>()?;
     info!("Starting HTTP server on {}", addr);
     let listener = tokio::net::TcpListener::bind(addr).await?;
 
-    // Run api and processor concurrently
-    tokio::select! {
-        result = axum::serve(listener, app).with_graceful_shutdown(shutdown_signal()) => {
-            if let Err(e) = result {
-                error!("HTTP server error: {}", e);
-            }
-        }
-        result = processor.start() => {
-            if let Err(e) = result {
-                error!("Processor service error: {}", e);
-            }
+    let server_processor = processor.clone();
+    // Peer addresses key the create rate limit
+    let result = axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        tokio::select! {
+            _ = shutdown_signal() => {}
+            _ = server_processor.shutdown_requested() => {}
         }
+    })
+    .await;
+    if let Err(e) = result {
+        error!("HTTP server error: {}", e);
     }
 
+    // Wait for the processor to finish its current reconciliation and pending removals
     processor.shutdown();
+    match processor_task.await {
+        Ok(Ok(())) => info!("Processor service stopped"),
+        Ok(Err(e)) => error!("Processor service error: {}", e),
+        Err(e) => error!("Processor task failed: {}", e),
+    }
 
     info!("Nebulet service stopped");
     Ok(())
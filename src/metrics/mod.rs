@@ -0,0 +1,41 @@
+use anyhow::Result;
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+
+use crate::models::v1::container::ContainerStatus;
+
+// Refer to the metrics crate with a leading `::` since this module shares its name
+pub fn install_recorder() -> Result<PrometheusHandle> {
+    let handle = PrometheusBuilder::new().install_recorder()?;
+
+    ::metrics::describe_counter!(
+        "containers_created_total",
+        "Number of containers created through the API"
+    );
+    ::metrics::describe_counter!(
+        "containers_failed_total",
+        "Number of container transitions to Failed"
+    );
+    ::metrics::describe_gauge!("containers_by_status", "Number of containers per status");
+
+    Ok(handle)
+}
+
+pub fn record_container_created() {
+    ::metrics::counter!("containers_created_total").increment(1);
+}
+
+pub fn record_container_failed() {
+    ::metrics::counter!("containers_failed_total").increment(1);
+}
+
+pub fn set_containers_by_status(counts: &[(ContainerStatus, u64)]) {
+    // Reset every status so ones without rows drop back to zero
+    for status in ContainerStatus::ALL {
+        let count = counts
+            .iter()
+            .find(|(counted, _)| *counted == status)
+            .map(|(_, count)| *count)
+            .unwrap_or(0);
+        ::metrics::gauge!("containers_by_status", "status" => status.as_str()).set(count as f64);
+    }
+}
@@ -0,0 +1,84 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use sea_orm::{entity::prelude::*, ActiveModelTrait, ConnectionTrait, Set};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ContainerEventResponse {
+    pub id: String,
+    pub container_id: String,
+    /// None for the event that created the container
+    pub from_status: Option<String>,
+    pub to_status: String,
+    pub timestamp: DateTime<Utc>,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ContainerEventsResponse {
+    pub id: String,
+    pub events: Vec<ContainerEventResponse>,
+}
+
+// Database Model
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "audit_log")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub container_id: String,
+    pub from_status: Option<String>,
+    pub to_status: String,
+    #[sea_orm(column_type = "Text")]
+    pub timestamp: String,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub reason: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl From<Model> for ContainerEventResponse {
+    fn from(model: Model) -> Self {
+        Self {
+            id: model.id,
+            container_id: model.container_id,
+            from_status: model.from_status,
+            to_status: model.to_status,
+            timestamp: DateTime::parse_from_rfc3339(&model.timestamp)
+                .map(|dt| dt.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+            reason: model.reason,
+        }
+    }
+}
+
+// Callers pass the transaction that changes the status so the log can't drift from it
+pub async fn record_transition<C: ConnectionTrait>(
+    db: &C,
+    container_id: &str,
+    from_status: Option<&str>,
+    to_status: &str,
+    reason: Option<String>,
+) -> Result<(), DbErr> {
+    if from_status == Some(to_status) {
+        return Ok(());
+    }
+
+    ActiveModel {
+        id: Set(Uuid::new_v4().to_string()),
+        container_id: Set(container_id.to_string()),
+        from_status: Set(from_status.map(str::to_string)),
+        to_status: Set(to_status.to_string()),
+        // Fixed precision keeps the text column ordered chronologically
+        timestamp: Set(Utc::now().to_rfc3339_opts(SecondsFormat::Nanos, true)),
+        reason: Set(reason),
+    }
+    .insert(db)
+    .await?;
+
+    Ok(())
+}
@@ -1,25 +1,766 @@
 use chrono::{DateTime, Utc};
-use sea_orm::{entity::prelude::*, Set};
-use serde::{Deserialize, Serialize};
+use regex::Regex;
+use sea_orm::{entity::prelude::*, Order, Set};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::LazyLock;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+use crate::api::error::ErrorResponse;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, ToSchema)]
 pub struct CreateContainerRequest {
     pub name: String,
     pub image: String,
+    /// Environment variables in KEY=VALUE format
+    pub env: Option<Vec<String>>,
+    pub ports: Option<Vec<PortMapping>>,
+    /// Bind mounts in host:container[:ro|rw] format
+    pub volumes: Option<Vec<String>>,
+    /// Overrides the image's default command, empty means use the default
+    pub command: Option<Vec<String>>,
+    /// Overrides the image's entrypoint, empty clears it so command runs directly
+    pub entrypoint: Option<Vec<String>>,
+    pub labels: Option<HashMap<String, String>>,
+    pub memory_mb: Option<u64>,
+    pub cpu_shares: Option<u64>,
+    /// Seconds Docker waits after SIGTERM before killing the container
+    pub stop_timeout_secs: Option<u64>,
+    /// "no" (default), "on-failure" or "always"
+    pub restart_policy: Option<String>,
+    /// Existing Docker network to attach the container to instead of the default bridge
+    pub network: Option<String>,
+    /// Replaces the image's HEALTHCHECK, or adds one to images without it
+    pub healthcheck: Option<HealthcheckSpec>,
+    /// Remove the container once it exits, like `docker run --rm`
+    #[serde(default)]
+    pub auto_remove: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
+pub struct HealthcheckSpec {
+    /// Docker test form, e.g. ["CMD", "curl", "-f", "http://localhost"] or
+    /// ["CMD-SHELL", "pg_isready"]
+    pub test: Vec<String>,
+    /// Seconds between checks, Docker's default is 30
+    pub interval_secs: Option<u64>,
+    /// Seconds before a check counts as failed, Docker's default is 30
+    pub timeout_secs: Option<u64>,
+    /// Consecutive failures before the container is unhealthy, Docker's default is 3
+    pub retries: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
+pub struct PortMapping {
+    pub container_port: u32,
+    pub host_port: u32,
+    /// "tcp" (default), "udp" or "sctp"
+    pub protocol: Option<String>,
+}
+
+impl PortMapping {
+    pub fn protocol(&self) -> &str {
+        self.protocol.as_deref().unwrap_or("tcp")
+    }
+}
+
+const MAX_STOP_TIMEOUT_SECS: u64 = 3600;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub(crate) fn new(field: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+// Same character set Docker accepts for container names
+static CONTAINER_NAME: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[a-zA-Z0-9][a-zA-Z0-9_.-]+$").expect("container name regex is valid")
+});
+
+// Rejected up front so overlong names don't only fail once the processor creates them
+const MAX_NAME_LEN: usize = 255;
+
+pub(crate) fn check_name(name: &str, errors: &mut Vec<FieldError>) {
+    if name.is_empty() {
+        errors.push(FieldError::new("name", "must not be empty"));
+    } else if name.len() > MAX_NAME_LEN {
+        errors.push(FieldError::new(
+            "name",
+            format!("must be at most {} characters", MAX_NAME_LEN),
+        ));
+    } else if !CONTAINER_NAME.is_match(name) {
+        errors.push(FieldError::new(
+            "name",
+            "must match ^[a-zA-Z0-9][a-zA-Z0-9_.-]+$",
+        ));
+    }
+}
+
+// Docker drops entries without a "=" and fails on some invalid names, both only show up
+// once the container runs. Values may contain anything, newlines included
+static ENV_ENTRY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*=").expect("env entry regex is valid"));
+
+// Each offending entry is reported by its index, values may hold secrets
+fn check_env(env: &[String], errors: &mut Vec<FieldError>) {
+    for (index, entry) in env.iter().enumerate() {
+        if !ENV_ENTRY.is_match(entry) {
+            errors.push(FieldError::new(
+                &format!("env[{}]", index),
+                "must match ^[A-Za-z_][A-Za-z0-9_]*=.*$",
+            ));
+        }
+    }
+}
+
+fn check_image(image: &str, errors: &mut Vec<FieldError>) {
+    if image.is_empty() {
+        errors.push(FieldError::new("image", "must not be empty"));
+    }
+}
+
+impl CreateContainerRequest {
+    /// Field-level checks that run before anything touches the database
+    pub fn validate_fields(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        check_name(&self.name, &mut errors);
+        check_image(&self.image, &mut errors);
+        if let Some(env) = &self.env {
+            check_env(env, &mut errors);
+        }
+        if self.entrypoint.as_ref().is_some_and(Vec::is_empty)
+            && self.command.as_ref().is_some_and(Vec::is_empty)
+        {
+            errors.push(FieldError::new(
+                "entrypoint",
+                "must not be empty when command is empty too",
+            ));
+        }
+        if self.network.as_deref() == Some("") {
+            errors.push(FieldError::new("network", "must not be empty"));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        validate_image_reference(&self.image)?;
+
+        if let Some(ports) = &self.ports {
+            let mut seen = std::collections::HashSet::new();
+            for port in ports {
+                if !(1..=65535).contains(&port.host_port) {
+                    return Err(format!(
+                        "Host port {} is out of range 1-65535",
+                        port.host_port
+                    ));
+                }
+                if !(1..=65535).contains(&port.container_port) {
+                    return Err(format!(
+                        "Container port {} is out of range 1-65535",
+                        port.container_port
+                    ));
+                }
+                if !matches!(port.protocol(), "tcp" | "udp" | "sctp") {
+                    return Err(format!("Unsupported port protocol: {}", port.protocol()));
+                }
+                if !seen.insert((port.host_port, port.protocol().to_string())) {
+                    return Err(format!(
+                        "Duplicate host port mapping: {}/{}",
+                        port.host_port,
+                        port.protocol()
+                    ));
+                }
+            }
+        }
+
+        for volume in self.volumes.iter().flatten() {
+            validate_volume(volume)?;
+        }
+
+        if self.labels.iter().flatten().any(|(key, _)| key.is_empty()) {
+            return Err("Label keys must not be empty".to_string());
+        }
+
+        if let Some(stop_timeout_secs) = self.stop_timeout_secs {
+            if stop_timeout_secs > MAX_STOP_TIMEOUT_SECS {
+                return Err(format!(
+                    "Stop timeout {}s exceeds the maximum of {}s",
+                    stop_timeout_secs, MAX_STOP_TIMEOUT_SECS
+                ));
+            }
+        }
+
+        if let Some(policy) = &self.restart_policy {
+            if !matches!(policy.as_str(), "no" | "on-failure" | "always") {
+                return Err(format!("Unsupported restart policy: {}", policy));
+            }
+            if self.auto_remove && policy != "no" {
+                return Err(format!(
+                    "Restart policy {} can't be combined with auto_remove",
+                    policy
+                ));
+            }
+        }
+
+        if let Some(healthcheck) = &self.healthcheck {
+            validate_healthcheck(healthcheck)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn validate_healthcheck(healthcheck: &HealthcheckSpec) -> Result<(), String> {
+    match healthcheck.test.first().map(String::as_str) {
+        Some("CMD" | "CMD-SHELL") if healthcheck.test.len() > 1 => {}
+        Some("NONE") if healthcheck.test.len() == 1 => {}
+        _ => {
+            return Err(
+                "Healthcheck test must be [\"CMD\", ...], [\"CMD-SHELL\", command] or [\"NONE\"]"
+                    .to_string(),
+            )
+        }
+    }
+    if healthcheck.interval_secs == Some(0) {
+        return Err("Healthcheck interval must be a positive number of seconds".to_string());
+    }
+    if healthcheck.timeout_secs == Some(0) {
+        return Err("Healthcheck timeout must be a positive number of seconds".to_string());
+    }
+    Ok(())
+}
+
+fn validate_volume(volume: &str) -> Result<(), String> {
+    let parts: Vec<&str> = volume.split(':').collect();
+    let (host, container) = match parts.as_slice() {
+        [host, container] => (*host, *container),
+        [host, container, "ro" | "rw"] => (*host, *container),
+        [_, _, mode] => return Err(format!("Invalid volume mode '{}' in {}", mode, volume)),
+        _ => {
+            return Err(format!(
+                "Invalid volume '{}', expected host:container[:ro|rw]",
+                volume
+            ))
+        }
+    };
+
+    if host.is_empty() || container.is_empty() {
+        return Err(format!("Invalid volume '{}', empty path", volume));
+    }
+    if !container.starts_with('/') {
+        return Err(format!(
+            "Invalid volume '{}', container path must be absolute",
+            volume
+        ));
+    }
+    if host
+        .split('/')
+        .chain(container.split('/'))
+        .any(|part| part == "..")
+    {
+        return Err(format!(
+            "Invalid volume '{}', '..' is not allowed in paths",
+            volume
+        ));
+    }
+
+    Ok(())
+}
+
+// Docker reference grammar: [domain[:port]/]path[:tag][@digest], path components lowercase
+static IMAGE_REFERENCE: LazyLock<Regex> = LazyLock::new(|| {
+    let component = r"[a-z0-9]+(?:(?:[._]|__|-+)[a-z0-9]+)*";
+    let domain_label = r"[a-zA-Z0-9](?:[a-zA-Z0-9-]*[a-zA-Z0-9])?";
+    Regex::new(&format!(
+        r"^(?:{label}(?:\.{label})*(?::[0-9]+)?/)?{component}(?:/{component})*(?::[\w][\w.-]{{0,127}})?(?:@[A-Za-z][A-Za-z0-9]*(?:[-_+.][A-Za-z][A-Za-z0-9]*)*:[0-9a-fA-F]{{32,}})?$",
+        label = domain_label,
+        component = component,
+    ))
+    .expect("image reference regex is valid")
+});
+
+pub fn validate_image_reference(image: &str) -> Result<(), String> {
+    if image.len() > 255 || !IMAGE_REFERENCE.is_match(image) {
+        return Err(format!(
+            "Invalid image reference '{}', expected name[:tag][@digest]",
+            image
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BatchCreateResult {
+    /// Position of the request in the submitted array
+    pub index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container: Option<ContainerResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorResponse>,
+}
+
+/// Every item on success, only the failing items when the batch was rejected
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BatchCreateResponse {
+    pub items: Vec<BatchCreateResult>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, ToSchema)]
+pub struct UpdateContainerRequest {
+    /// Renames the Docker container right away, without recreating it
+    pub name: Option<String>,
+    /// Recreates a Created, Running or Paused container right away through Pending, any
+    /// other keeps its status and picks the image up on its next start
+    pub image: Option<String>,
+    /// Replaces all environment variables, the processor recreates the container to apply them
+    pub env: Option<Vec<String>>,
+}
+
+impl UpdateContainerRequest {
+    pub fn validate_fields(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        if let Some(name) = &self.name {
+            check_name(name, &mut errors);
+        }
+        if let Some(image) = &self.image {
+            check_image(image, &mut errors);
+        }
+        if let Some(env) = &self.env {
+            check_env(env, &mut errors);
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(image) = &self.image {
+            validate_image_reference(image)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerAction {
+    Start,
+    Stop,
+    Restart,
+    Pause,
+    Unpause,
+}
+
+impl ContainerAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContainerAction::Start => "start",
+            ContainerAction::Stop => "stop",
+            ContainerAction::Restart => "restart",
+            ContainerAction::Pause => "pause",
+            ContainerAction::Unpause => "unpause",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ContainerActionRequest {
+    pub action: ContainerAction,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListContainersQuery {
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+    /// Comma-separated list of statuses, e.g. "Running,Failed"
+    pub status: Option<String>,
+    /// Comma-separated key=value selectors that must all match, e.g. "env=prod,team=core"
+    pub label: Option<String>,
+    /// Exact image reference, or a prefix when it ends in "*", e.g. "nginx:*"
+    pub image: Option<String>,
+    /// "created_at" (default), "updated_at", "name" or "status"
+    pub sort: Option<String>,
+    /// "desc" (default) or "asc"
+    pub order: Option<String>,
+    /// Also return containers that were removed, defaults to false
+    pub include_deleted: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CreateContainerQuery {
+    /// Only validate the request and return the container that would be created. Docker
+    /// isn't contacted, so the network isn't checked
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetContainerQuery {
+    /// Also return the container when it was removed, defaults to false
+    pub include_deleted: Option<bool>,
+}
+
+fn parse_statuses(status: &Option<String>) -> Result<Vec<ContainerStatus>, String> {
+    status
+        .iter()
+        .flat_map(|status| status.split(','))
+        .map(str::trim)
+        .filter(|status| !status.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+impl ListContainersQuery {
+    pub fn statuses(&self) -> Result<Vec<ContainerStatus>, String> {
+        parse_statuses(&self.status)
+    }
+
+    pub fn sort_column(&self) -> Result<Column, String> {
+        match self.sort.as_deref().unwrap_or("created_at") {
+            "created_at" => Ok(Column::CreatedAt),
+            "updated_at" => Ok(Column::UpdatedAt),
+            "name" => Ok(Column::Name),
+            "status" => Ok(Column::Status),
+            other => Err(format!(
+                "Unsupported sort field: {}, expected created_at, updated_at, name or status",
+                other
+            )),
+        }
+    }
+
+    pub fn sort_order(&self) -> Result<Order, String> {
+        match self.order.as_deref().unwrap_or("desc") {
+            "desc" => Ok(Order::Desc),
+            "asc" => Ok(Order::Asc),
+            other => Err(format!(
+                "Unsupported sort order: {}, expected asc or desc",
+                other
+            )),
+        }
+    }
+
+    pub fn labels(&self) -> Result<Vec<(String, String)>, String> {
+        parse_labels(&self.label)
+    }
+}
+
+fn parse_labels(label: &Option<String>) -> Result<Vec<(String, String)>, String> {
+    label
+        .iter()
+        .flat_map(|label| label.split(','))
+        .map(str::trim)
+        .filter(|selector| !selector.is_empty())
+        .map(|selector| match selector.split_once('=') {
+            Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+            _ => Err(format!(
+                "Invalid label selector '{}', expected key=value",
+                selector
+            )),
+        })
+        .collect()
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PruneContainersQuery {
+    /// Required comma-separated list of statuses to prune, e.g. "Stopped,Failed"
+    pub status: Option<String>,
+    /// Only prune containers not updated for this long, e.g. "30m", "12h" or "7d"
+    pub older_than: Option<String>,
+}
+
+impl PruneContainersQuery {
+    pub fn statuses(&self) -> Result<Vec<ContainerStatus>, String> {
+        let statuses = parse_statuses(&self.status)?;
+        if statuses.is_empty() {
+            return Err("status is required to prune containers".to_string());
+        }
+        if statuses.contains(&ContainerStatus::Removing) {
+            return Err("Removing containers are already being pruned".to_string());
+        }
+        if statuses.contains(&ContainerStatus::Removed) {
+            return Err("Removed containers cannot be pruned".to_string());
+        }
+        Ok(statuses)
+    }
+
+    pub fn older_than(&self) -> Result<Option<chrono::Duration>, String> {
+        self.older_than.as_deref().map(parse_duration).transpose()
+    }
+}
+
+// Parses "<n>s", "<n>m", "<n>h" or "<n>d"
+fn parse_duration(value: &str) -> Result<chrono::Duration, String> {
+    let invalid = || {
+        format!(
+            "Invalid duration '{}', expected e.g. 30s, 15m, 12h or 7d",
+            value
+        )
+    };
+    let value = value.trim();
+    let (split, _) = value.char_indices().last().ok_or_else(invalid)?;
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+    let seconds = match unit {
+        "s" => Some(amount),
+        "m" => amount.checked_mul(60),
+        "h" => amount.checked_mul(3600),
+        "d" => amount.checked_mul(86400),
+        _ => None,
+    };
+    seconds
+        .filter(|seconds| *seconds >= 0)
+        .and_then(chrono::Duration::try_seconds)
+        .ok_or_else(invalid)
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PruneContainersResponse {
+    /// Number of containers marked for removal
+    pub count: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ClusterStatsQuery {
+    /// How far back failures are counted, e.g. "30m" or "1d", defaults to "1h"
+    pub failures_window: Option<String>,
+}
+
+impl ClusterStatsQuery {
+    pub fn failures_window(&self) -> Result<chrono::Duration, String> {
+        match self.failures_window.as_deref() {
+            Some(window) => parse_duration(window),
+            None => Ok(chrono::Duration::hours(1)),
+        }
+    }
+}
+
+/// Summary for dashboards, removed containers are not counted
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ClusterStats {
+    pub total: u64,
+    /// Containers per status, every status is present even at zero
+    pub by_status: BTreeMap<String, u64>,
+    /// Transitions to Failed within the window
+    pub failures: u64,
+    pub failures_window_secs: i64,
+}
+
+// LIKE pattern matching one "key":"value" pair inside the JSON encoded labels column
+pub fn label_like_pattern(key: &str, value: &str) -> String {
+    let pair = format!(
+        "{}:{}",
+        serde_json::Value::from(key),
+        serde_json::Value::from(value)
+    );
+    format!("%{}%", escape_like(&pair))
+}
+
+// LIKE pattern matching a ports column that maps the host port, over any protocol.
+// Serialized mappings always continue with the protocol after the host port.
+pub fn host_port_like_pattern(host_port: u32) -> String {
+    format!(
+        "%{}%",
+        escape_like(&format!("\"host_port\":{},", host_port))
+    )
+}
+
+// LIKE pattern matching every value starting with the prefix
+pub fn prefix_like_pattern(prefix: &str) -> String {
+    format!("{}%", escape_like(prefix))
+}
+
+// Escapes LIKE wildcards, queries using the result need ESCAPE '\'
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ContainerListResponse {
+    pub items: Vec<ContainerResponse>,
+    pub total: u64,
+    pub limit: u64,
+    pub offset: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ContainerLogsQuery {
+    pub tail: Option<usize>,
+    pub timestamps: Option<bool>,
+    pub follow: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ContainerLogsResponse {
+    pub id: String,
+    pub lines: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AggregateLogsQuery {
+    /// Required comma-separated key=value selectors that must all match, e.g. "app=web"
+    pub label: Option<String>,
+    /// Lines taken from each container before merging
+    pub tail: Option<usize>,
+    pub timestamps: Option<bool>,
+}
+
+impl AggregateLogsQuery {
+    pub fn labels(&self) -> Result<Vec<(String, String)>, String> {
+        let labels = parse_labels(&self.label)?;
+        if labels.is_empty() {
+            return Err("label is required to aggregate logs".to_string());
+        }
+        Ok(labels)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AggregateLogLine {
+    pub container_id: String,
+    pub container_name: String,
+    pub line: String,
+}
+
+/// Log lines of every matching container, oldest first
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AggregateLogsResponse {
+    /// Matching containers, including those without any logs
+    pub containers: u64,
+    pub lines: Vec<AggregateLogLine>,
+    /// Older lines were dropped to stay within the response size cap
+    pub truncated: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct ContainerStats {
+    pub cpu_percent: f64,
+    pub memory_usage_bytes: u64,
+    pub memory_limit_bytes: u64,
+    pub memory_percent: f64,
+}
+
+/// Process table of a running container, as ps inside it would list it
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct ContainerTop {
+    /// Column headers, e.g. UID, PID and CMD
+    pub titles: Vec<String>,
+    /// One row per process, with values in the order of titles
+    pub processes: Vec<Vec<String>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ExecRequest {
+    /// Command and arguments, run without a shell
+    pub cmd: Vec<String>,
+}
+
+impl ExecRequest {
+    pub fn validate_fields(&self) -> Result<(), Vec<FieldError>> {
+        if self.cmd.is_empty() || self.cmd[0].is_empty() {
+            return Err(vec![FieldError::new("cmd", "must not be empty")]);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct ExecResponse {
+    /// Combined stdout and stderr
+    pub output: String,
+    /// None when the output was truncated before the command finished
+    pub exit_code: Option<i64>,
+    pub truncated: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ContainerResponse {
     pub id: String,
     pub name: String,
     pub image: String,
     pub status: ContainerStatus,
+    pub env: Option<Vec<String>>,
+    pub ports: Option<Vec<PortMapping>>,
+    pub volumes: Option<Vec<String>>,
+    pub command: Option<Vec<String>>,
+    pub entrypoint: Option<Vec<String>>,
+    pub labels: Option<HashMap<String, String>>,
+    pub memory_mb: Option<u64>,
+    pub cpu_shares: Option<u64>,
+    pub stop_timeout_secs: Option<u64>,
+    pub restart_policy: Option<String>,
+    pub restart_count: u64,
+    pub network: Option<String>,
+    /// Attempts the last Docker create, start or stop took, including retries
+    pub docker_attempts: u64,
+    /// Exit code from the last time the container exited, None while it never has
+    pub exit_code: Option<i64>,
+    /// Name of the processor currently reconciling the container
+    pub owner: Option<String>,
+    /// Digest the image resolved to when the Docker container was created, e.g.
+    /// "nginx@sha256:...", None before then or for images without a registry digest
+    pub image_digest: Option<String>,
+    /// When Docker last started the container, None while it never ran
+    pub started_at: Option<DateTime<Utc>>,
+    /// When the container last stopped running, None while it is up
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Seconds the container has been up, frozen at the last run's length once it stops
+    pub uptime_secs: Option<i64>,
+    pub healthcheck: Option<HealthcheckSpec>,
+    pub auto_remove: bool,
+    /// "starting", "healthy" or "unhealthy" while Running, only for images with a HEALTHCHECK
+    pub health: Option<String>,
+    pub error_message: Option<String>,
+    /// When the container entered its current status
+    pub status_changed_at: Option<DateTime<Utc>>,
+    /// When the record was created, i.e. when nebulet accepted the request
     pub created_at: DateTime<Utc>,
+    /// When Docker created the current container, None before the processor created it
+    pub docker_created_at: Option<DateTime<Utc>>,
     pub updated_at: DateTime<Utc>,
+    /// When the processor finished removing the container, None while it still exists
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+/// Message pushed over `/v1/containers/{id}/watch`, first for the current status and
+/// then for every status change
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ContainerStatusUpdate {
+    pub id: String,
+    pub status: ContainerStatus,
+    pub error_message: Option<String>,
+    pub exit_code: Option<i64>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
 pub enum ContainerStatus {
     Pending,
     Created,
@@ -27,9 +768,38 @@ pub enum ContainerStatus {
     Stopped,
     Failed,
     Removing,
+    Restarting,
+    Pulling,
+    Removed,
+    Paused,
 }
 
 impl ContainerStatus {
+    pub const ALL: [ContainerStatus; 10] = [
+        ContainerStatus::Pending,
+        ContainerStatus::Created,
+        ContainerStatus::Running,
+        ContainerStatus::Stopped,
+        ContainerStatus::Failed,
+        ContainerStatus::Removing,
+        ContainerStatus::Restarting,
+        ContainerStatus::Pulling,
+        ContainerStatus::Removed,
+        ContainerStatus::Paused,
+    ];
+
+    // Status recorded for a live Docker state. None for exits, which go through the restart
+    // policy, and for transient states like "restarting" and "removing". Docker restarting
+    // a container on its own is not a requested Restarting, which would restart it again
+    pub fn from_docker_state(state: &str) -> Option<ContainerStatus> {
+        match state {
+            "created" => Some(ContainerStatus::Created),
+            "running" => Some(ContainerStatus::Running),
+            "paused" => Some(ContainerStatus::Paused),
+            _ => None,
+        }
+    }
+
     pub fn as_str(&self) -> &'static str {
         match self {
             ContainerStatus::Pending => "Pending",
@@ -38,6 +808,30 @@ impl ContainerStatus {
             ContainerStatus::Stopped => "Stopped",
             ContainerStatus::Failed => "Failed",
             ContainerStatus::Removing => "Removing",
+            ContainerStatus::Restarting => "Restarting",
+            ContainerStatus::Pulling => "Pulling",
+            ContainerStatus::Removed => "Removed",
+            ContainerStatus::Paused => "Paused",
+        }
+    }
+}
+
+impl std::str::FromStr for ContainerStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "Pending" => Ok(ContainerStatus::Pending),
+            "Created" => Ok(ContainerStatus::Created),
+            "Running" => Ok(ContainerStatus::Running),
+            "Stopped" => Ok(ContainerStatus::Stopped),
+            "Failed" => Ok(ContainerStatus::Failed),
+            "Removing" => Ok(ContainerStatus::Removing),
+            "Restarting" => Ok(ContainerStatus::Restarting),
+            "Pulling" => Ok(ContainerStatus::Pulling),
+            "Removed" => Ok(ContainerStatus::Removed),
+            "Paused" => Ok(ContainerStatus::Paused),
+            _ => Err(format!("Unknown container status: {}", value)),
         }
     }
 }
@@ -52,10 +846,63 @@ pub struct Model {
     pub image: String,
     pub status: String,
     pub docker_id: Option<String>,
-    #[sea_orm(column_type = "Text")]
-    pub created_at: String,
-    #[sea_orm(column_type = "Text")]
-    pub updated_at: String,
+    // JSON encoded Vec<String>
+    #[sea_orm(column_type = "Text", nullable)]
+    pub env: Option<String>,
+    // JSON encoded Vec<PortMapping>
+    #[sea_orm(column_type = "Text", nullable)]
+    pub ports: Option<String>,
+    // JSON encoded Vec<String>
+    #[sea_orm(column_type = "Text", nullable)]
+    pub volumes: Option<String>,
+    pub memory_mb: Option<i64>,
+    pub cpu_shares: Option<i64>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub error_message: Option<String>,
+    pub restart_policy: Option<String>,
+    pub restart_count: i64,
+    // JSON encoded Vec<String>
+    #[sea_orm(column_type = "Text", nullable)]
+    pub command: Option<String>,
+    // JSON encoded Vec<String>, unlike command an empty list is kept since it clears the
+    // image's entrypoint
+    #[sea_orm(column_type = "Text", nullable)]
+    pub entrypoint: Option<String>,
+    // JSON encoded HashMap<String, String>
+    #[sea_orm(column_type = "Text", nullable)]
+    pub labels: Option<String>,
+    pub stop_timeout_secs: Option<i64>,
+    // Bumped on every write, conditional updates use it to detect concurrent changes
+    pub version: i64,
+    pub docker_attempts: i64,
+    pub network: Option<String>,
+    pub exit_code: Option<i64>,
+    // Processor that reconciles the container, another one may take over once the
+    // owner's heartbeat in processor_heartbeats goes stale
+    pub owner: Option<String>,
+    pub created_at: DateTimeUtc,
+    pub updated_at: DateTimeUtc,
+    // Set once the processor removed the Docker container, the row is kept for history
+    pub deleted_at: Option<DateTimeUtc>,
+    // RepoDigests entry recorded on create, cleared only when the image changes
+    pub image_digest: Option<String>,
+    // Docker's StartedAt from the last transition into Running
+    pub started_at: Option<DateTimeUtc>,
+    // Set when the container leaves Running or Paused, cleared on the next start
+    pub finished_at: Option<DateTimeUtc>,
+    // Docker's healthcheck status as of the last poll, None without a HEALTHCHECK
+    pub health: Option<String>,
+    // JSON encoded HealthcheckSpec
+    #[sea_orm(column_type = "Text", nullable)]
+    pub healthcheck: Option<String>,
+    // Docker's Created time for the current Docker container, replaced on recreate
+    pub docker_created_at: Option<DateTimeUtc>,
+    // Docker removes the container when it exits, the row then goes straight to Removed
+    pub auto_remove: bool,
+    // Only moves when the status does, unlike updated_at
+    pub status_changed_at: Option<DateTimeUtc>,
+    // desired_spec_hash() as of the Docker container's creation, None for older rows
+    pub spec_hash: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -66,48 +913,169 @@ impl ActiveModelBehavior for ActiveModel {}
 // Using standard From trait for API -> Database conversion
 impl From<CreateContainerRequest> for Model {
     fn from(api_model: CreateContainerRequest) -> Self {
-        let now = Utc::now().to_rfc3339();
+        let now = Utc::now();
         Self {
             id: Uuid::new_v4().to_string(),
             name: api_model.name,
             image: api_model.image,
             status: ContainerStatus::Pending.as_str().to_string(),
             docker_id: None,
-            created_at: now.clone(),
+            env: to_json_column(&api_model.env),
+            ports: to_json_column(&api_model.ports),
+            volumes: to_json_column(&api_model.volumes),
+            command: to_json_column(&api_model.command.filter(|command| !command.is_empty())),
+            entrypoint: to_json_column(&api_model.entrypoint),
+            labels: to_json_column(&api_model.labels),
+            healthcheck: to_json_column(&api_model.healthcheck),
+            stop_timeout_secs: api_model.stop_timeout_secs.map(|timeout| timeout as i64),
+            memory_mb: api_model.memory_mb.map(|memory_mb| memory_mb as i64),
+            cpu_shares: api_model.cpu_shares.map(|cpu_shares| cpu_shares as i64),
+            error_message: None,
+            restart_policy: api_model.restart_policy,
+            restart_count: 0,
+            version: 0,
+            docker_attempts: 0,
+            network: api_model.network,
+            exit_code: None,
+            owner: None,
+            created_at: now,
             updated_at: now,
+            deleted_at: None,
+            image_digest: None,
+            started_at: None,
+            finished_at: None,
+            health: None,
+            docker_created_at: None,
+            auto_remove: api_model.auto_remove,
+            status_changed_at: Some(now),
+            spec_hash: None,
         }
     }
 }
 
 impl From<Model> for ContainerResponse {
     fn from(model: Model) -> Self {
+        let uptime_secs = model.started_at.map(|started_at| {
+            let until = model.finished_at.unwrap_or_else(Utc::now);
+            (until - started_at).num_seconds().max(0)
+        });
         Self {
             id: model.id,
             name: model.name,
             image: model.image,
-            status: match model.status.as_str() {
-                "Pending" => ContainerStatus::Pending,
-                "Created" => ContainerStatus::Created,
-                "Running" => ContainerStatus::Running,
-                "Stopped" => ContainerStatus::Stopped,
-                "Failed" => ContainerStatus::Failed,
-                "Removing" => ContainerStatus::Removing,
-                _ => ContainerStatus::Pending,
-            },
-            created_at: DateTime::parse_from_rfc3339(&model.created_at)
-                .map(|dt| dt.with_timezone(&Utc))
-                .unwrap_or_else(|_| Utc::now()),
-            updated_at: DateTime::parse_from_rfc3339(&model.updated_at)
-                .map(|dt| dt.with_timezone(&Utc))
-                .unwrap_or_else(|_| Utc::now()),
+            status: model.status.parse().unwrap_or(ContainerStatus::Pending),
+            env: from_json_column(&model.env),
+            ports: from_json_column(&model.ports),
+            volumes: from_json_column(&model.volumes),
+            command: from_json_column(&model.command),
+            entrypoint: from_json_column(&model.entrypoint),
+            labels: from_json_column(&model.labels),
+            memory_mb: model.memory_mb.map(|memory_mb| memory_mb as u64),
+            cpu_shares: model.cpu_shares.map(|cpu_shares| cpu_shares as u64),
+            stop_timeout_secs: model.stop_timeout_secs.map(|timeout| timeout as u64),
+            restart_policy: model.restart_policy,
+            restart_count: model.restart_count as u64,
+            network: model.network,
+            docker_attempts: model.docker_attempts as u64,
+            exit_code: model.exit_code,
+            owner: model.owner,
+            image_digest: model.image_digest,
+            started_at: model.started_at,
+            finished_at: model.finished_at,
+            uptime_secs,
+            healthcheck: from_json_column(&model.healthcheck),
+            auto_remove: model.auto_remove,
+            health: model.health.filter(|_| model.status == "Running"),
+            error_message: model.error_message,
+            status_changed_at: model.status_changed_at,
+            created_at: model.created_at,
+            docker_created_at: model.docker_created_at,
+            updated_at: model.updated_at,
+            deleted_at: model.deleted_at,
+        }
+    }
+}
+
+impl From<&Model> for ContainerStatusUpdate {
+    fn from(model: &Model) -> Self {
+        Self {
+            id: model.id.clone(),
+            status: model.status.parse().unwrap_or(ContainerStatus::Pending),
+            error_message: model.error_message.clone(),
+            exit_code: model.exit_code,
+            updated_at: model.updated_at,
         }
     }
 }
 
+// Database -> Docker conversion used by the processor
+impl From<&Model> for CreateContainerRequest {
+    fn from(model: &Model) -> Self {
+        Self {
+            name: model.name.clone(),
+            image: model.image.clone(),
+            env: from_json_column(&model.env),
+            ports: from_json_column(&model.ports),
+            volumes: from_json_column(&model.volumes),
+            command: from_json_column(&model.command),
+            entrypoint: from_json_column(&model.entrypoint),
+            labels: from_json_column(&model.labels),
+            memory_mb: model.memory_mb.map(|memory_mb| memory_mb as u64),
+            cpu_shares: model.cpu_shares.map(|cpu_shares| cpu_shares as u64),
+            stop_timeout_secs: model.stop_timeout_secs.map(|timeout| timeout as u64),
+            restart_policy: model.restart_policy.clone(),
+            network: model.network.clone(),
+            healthcheck: from_json_column(&model.healthcheck),
+            auto_remove: model.auto_remove,
+        }
+    }
+}
+
+fn to_json_column<T: Serialize>(value: &Option<T>) -> Option<String> {
+    value
+        .as_ref()
+        .and_then(|value| serde_json::to_string(value).ok())
+}
+
+fn from_json_column<T: DeserializeOwned>(value: &Option<String>) -> Option<T> {
+    value
+        .as_deref()
+        .and_then(|value| serde_json::from_str(value).ok())
+}
+
 // Convenience methods for the Model
 impl Model {
     pub fn new(name: String, image: String) -> Self {
-        CreateContainerRequest { name, image }.into()
+        CreateContainerRequest {
+            name,
+            image,
+            ..Default::default()
+        }
+        .into()
+    }
+
+    // Covers what is baked into the Docker container when it is created, a different hash
+    // than the recorded spec_hash means the container has to be recreated
+    pub fn desired_spec_hash(&self) -> String {
+        let mut spec = vec![
+            serde_json::json!(self.image),
+            serde_json::json!(self.env),
+            serde_json::json!(self.ports),
+            serde_json::json!(self.command),
+        ];
+        // Only hashed when set, so rows from before the column keep their hash
+        if let Some(entrypoint) = &self.entrypoint {
+            spec.push(serde_json::json!(entrypoint));
+        }
+        format!(
+            "{:x}",
+            Sha256::digest(serde_json::Value::from(spec).to_string())
+        )
+    }
+
+    // Decoded ports column, a row that can't be decoded maps no ports
+    pub fn port_mappings(&self) -> Vec<PortMapping> {
+        from_json_column(&self.ports).unwrap_or_default()
     }
 
     // You can still keep this method for explicit conversion
@@ -117,12 +1085,13 @@ impl Model {
 
     pub fn update_status(&mut self, status: ContainerStatus) {
         self.status = status.as_str().to_string();
-        self.updated_at = Utc::now().to_rfc3339();
+        self.updated_at = Utc::now();
+        self.status_changed_at = Some(self.updated_at);
     }
 
     pub fn set_docker_id(&mut self, docker_id: String) {
         self.docker_id = Some(docker_id);
-        self.updated_at = Utc::now().to_rfc3339();
+        self.updated_at = Utc::now();
     }
 
     // Helper method to create an ActiveModel for insertion
@@ -133,8 +1102,62 @@ impl Model {
             image: Set(self.image),
             status: Set(self.status),
             docker_id: Set(self.docker_id),
+            env: Set(self.env),
+            ports: Set(self.ports),
+            volumes: Set(self.volumes),
+            memory_mb: Set(self.memory_mb),
+            cpu_shares: Set(self.cpu_shares),
+            error_message: Set(self.error_message),
+            restart_policy: Set(self.restart_policy),
+            restart_count: Set(self.restart_count),
+            command: Set(self.command),
+            entrypoint: Set(self.entrypoint),
+            labels: Set(self.labels),
+            stop_timeout_secs: Set(self.stop_timeout_secs),
+            version: Set(self.version),
+            docker_attempts: Set(self.docker_attempts),
+            network: Set(self.network),
+            exit_code: Set(self.exit_code),
+            owner: Set(self.owner),
             created_at: Set(self.created_at),
             updated_at: Set(self.updated_at),
+            deleted_at: Set(self.deleted_at),
+            image_digest: Set(self.image_digest),
+            started_at: Set(self.started_at),
+            finished_at: Set(self.finished_at),
+            health: Set(self.health),
+            healthcheck: Set(self.healthcheck),
+            docker_created_at: Set(self.docker_created_at),
+            auto_remove: Set(self.auto_remove),
+            status_changed_at: Set(self.status_changed_at),
+            spec_hash: Set(self.spec_hash),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn docker_states_map_to_statuses() {
+        let cases = [
+            ("created", Some(ContainerStatus::Created)),
+            ("running", Some(ContainerStatus::Running)),
+            ("paused", Some(ContainerStatus::Paused)),
+            ("restarting", None),
+            ("removing", None),
+            ("exited", None),
+            ("dead", None),
+            ("unknown", None),
+        ];
+        for (state, expected) in cases {
+            assert_eq!(
+                ContainerStatus::from_docker_state(state),
+                expected,
+                "Docker state {}",
+                state
+            );
         }
     }
 }
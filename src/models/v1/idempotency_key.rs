@@ -0,0 +1,58 @@
+use chrono::{Duration, Utc};
+use sea_orm::{entity::prelude::*, ConnectionTrait, Set};
+use serde::{Deserialize, Serialize};
+
+use super::container::ContainerResponse;
+
+// Database Model
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "idempotency_keys")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub key: String,
+    pub container_id: String,
+    // JSON encoded ContainerResponse returned by the original request
+    #[sea_orm(column_type = "Text")]
+    pub response: String,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+/// Returns the response stored for `key`, dropping keys older than `ttl_secs` first
+pub async fn find_response<C: ConnectionTrait>(
+    db: &C,
+    key: &str,
+    ttl_secs: u64,
+) -> Result<Option<ContainerResponse>, DbErr> {
+    let cutoff = Utc::now() - Duration::seconds(ttl_secs as i64);
+    Entity::delete_many()
+        .filter(Column::CreatedAt.lt(cutoff))
+        .exec(db)
+        .await?;
+
+    let stored = Entity::find_by_id(key.to_string()).one(db).await?;
+    Ok(stored.and_then(|stored| serde_json::from_str(&stored.response).ok()))
+}
+
+pub async fn record<C: ConnectionTrait>(
+    db: &C,
+    key: &str,
+    response: &ContainerResponse,
+) -> Result<(), DbErr> {
+    let body = serde_json::to_string(response).map_err(|e| DbErr::Custom(e.to_string()))?;
+
+    ActiveModel {
+        key: Set(key.to_string()),
+        container_id: Set(response.id.clone()),
+        response: Set(body),
+        created_at: Set(Utc::now()),
+    }
+    .insert(db)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::container::validate_image_reference;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListImagesQuery {
+    /// Only untagged images no container or other image refers to, defaults to false
+    pub dangling: Option<bool>,
+}
+
+// Read straight from Docker, images aren't tracked in the database
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ImageResponse {
+    /// Content-addressable image id, e.g. "sha256:..."
+    pub id: String,
+    /// Tags referring to the image, empty for dangling images
+    pub repo_tags: Vec<String>,
+    pub size_bytes: u64,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ImageListResponse {
+    pub items: Vec<ImageResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, ToSchema)]
+pub struct PullImageRequest {
+    /// Image reference, e.g. "nginx:1.25", pulls "latest" without a tag
+    pub image: String,
+}
+
+impl PullImageRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_image_reference(&self.image)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct PullImageResponse {
+    pub image: String,
+    /// Registry digest the image resolved to, e.g. "nginx@sha256:..."
+    pub digest: Option<String>,
+}
@@ -1,4 +1,8 @@
+pub mod audit_log;
 pub mod container;
+pub mod idempotency_key;
+pub mod image;
+pub mod network;
 pub mod processor;
 
 pub use container::*;
@@ -0,0 +1,96 @@
+use chrono::{DateTime, Utc};
+use sea_orm::{entity::prelude::*, Set};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::container::{check_name, FieldError};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, ToSchema)]
+pub struct CreateNetworkRequest {
+    pub name: String,
+    /// Docker network driver, "bridge" when unset
+    pub driver: Option<String>,
+}
+
+impl CreateNetworkRequest {
+    pub fn validate_fields(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        check_name(&self.name, &mut errors);
+        if self.driver.as_deref() == Some("") {
+            errors.push(FieldError::new("driver", "must not be empty"));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct NetworkResponse {
+    pub id: String,
+    pub name: String,
+    pub driver: Option<String>,
+    pub docker_id: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct NetworkListResponse {
+    pub items: Vec<NetworkResponse>,
+}
+
+// Database Model, only networks created through the API are tracked and can be deleted
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "networks")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    #[sea_orm(unique)]
+    pub name: String,
+    pub driver: Option<String>,
+    pub docker_id: String,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    pub fn new(request: CreateNetworkRequest, docker_id: String) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name: request.name,
+            driver: request.driver,
+            docker_id,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn into_active_model(self) -> ActiveModel {
+        ActiveModel {
+            id: Set(self.id),
+            name: Set(self.name),
+            driver: Set(self.driver),
+            docker_id: Set(self.docker_id),
+            created_at: Set(self.created_at),
+        }
+    }
+}
+
+impl From<Model> for NetworkResponse {
+    fn from(model: Model) -> Self {
+        Self {
+            id: model.id,
+            name: model.name,
+            driver: model.driver,
+            docker_id: model.docker_id,
+            created_at: model.created_at,
+        }
+    }
+}
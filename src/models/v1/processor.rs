@@ -1,2 +1,36 @@
-// This module is kept for future processor functionality
-// Currently empty for MVP simplicity
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ProcessorResponse {
+    pub name: String,
+    pub started_at: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub heartbeat_age_secs: i64,
+    /// False once the heartbeat is older than the lease, its containers can then be taken over
+    pub active: bool,
+    /// Containers currently claimed by this processor
+    pub containers: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ProcessorListResponse {
+    pub items: Vec<ProcessorResponse>,
+}
+
+// Database Model, one row per processor name written on every heartbeat
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "processor_heartbeats")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub name: String,
+    pub started_at: DateTimeUtc,
+    pub last_seen: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
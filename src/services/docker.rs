@@ -1,47 +1,676 @@
-use crate::models::CreateContainerRequest;
+use crate::config::{Config as AppConfig, RegistryCredentials};
+use crate::models::image::ImageResponse;
+use crate::models::{
+    ContainerStats, ContainerTop, CreateContainerRequest, ExecResponse, HealthcheckSpec,
+};
 use anyhow::Result;
+use async_trait::async_trait;
+use bollard::auth::DockerCredentials;
 use bollard::container::{
-    Config, CreateContainerOptions, InspectContainerOptions, ListContainersOptions,
-    RemoveContainerOptions, StartContainerOptions, StopContainerOptions,
+    Config, CreateContainerOptions, InspectContainerOptions, ListContainersOptions, LogsOptions,
+    MemoryStatsStats, NetworkingConfig, RemoveContainerOptions, RenameContainerOptions,
+    StartContainerOptions, Stats, StatsOptions, StopContainerOptions, TopOptions,
+};
+use bollard::errors::Error as BollardError;
+use bollard::exec::{CreateExecOptions, StartExecResults};
+use bollard::image::{CreateImageOptions, ListImagesOptions};
+use bollard::models::{
+    ContainerInspectResponse, ContainerStateStatusEnum, EndpointSettings, HealthConfig,
+    HealthStatusEnum, HostConfig, PortBinding,
 };
-use bollard::Docker;
+use bollard::network::{CreateNetworkOptions, InspectNetworkOptions};
+use bollard::system::EventsOptions;
+use bollard::{Docker, API_DEFAULT_VERSION};
+use chrono::{DateTime, Datelike, Utc};
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt};
+use std::collections::HashMap;
 use std::default::Default;
-use tracing::{error, info};
+use std::future::Future;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
+
+/// Failures of DockerService calls, wrapped in anyhow::Error so callers can downcast to
+/// tell a missing image from an unreachable daemon or a conflict
+#[derive(Debug, thiserror::Error)]
+pub enum DockerError {
+    #[error("container name already in use: {0}")]
+    NameConflict(String),
+    #[error("network name already in use: {0}")]
+    NetworkNameConflict(String),
+    #[error("image not found: {0}")]
+    ImageNotFound(String),
+    #[error("registry rejected the credentials for image {0}")]
+    RegistryAuth(String),
+    #[error("no such container in Docker: {0}")]
+    ContainerNotFound(String),
+    #[error("Docker daemon unreachable: {0}")]
+    Unavailable(String),
+    #[error("Docker request timed out: {0}")]
+    Timeout(String),
+    #[error("Docker responded with {status}: {message}")]
+    Response { status: u16, message: String },
+    /// Error reported in the middle of a streamed response, e.g. a failing pull
+    #[error("{0}")]
+    Stream(String),
+    #[error(transparent)]
+    Other(BollardError),
+}
+
+impl From<BollardError> for DockerError {
+    fn from(error: BollardError) -> Self {
+        match error {
+            BollardError::RequestTimeoutError => DockerError::Timeout(error.to_string()),
+            e if is_connection_error(&e) => DockerError::Unavailable(e.to_string()),
+            BollardError::DockerResponseServerError {
+                status_code,
+                message,
+            } => DockerError::Response {
+                status: status_code,
+                message,
+            },
+            BollardError::DockerStreamError { error } => DockerError::Stream(error),
+            e => DockerError::Other(e),
+        }
+    }
+}
+
+// A 404 from a call addressing a container means Docker doesn't know it
+fn container_error(container: &str, error: BollardError) -> DockerError {
+    match error {
+        BollardError::DockerResponseServerError {
+            status_code: 404, ..
+        } => DockerError::ContainerNotFound(container.to_string()),
+        e => e.into(),
+    }
+}
+
+// Registries answer unknown images with a 404 and bad credentials with a 401 or 403
+fn pull_error(image: &str, error: BollardError) -> DockerError {
+    match error {
+        BollardError::DockerResponseServerError {
+            status_code,
+            message,
+        } => match (pull_message_error(image, &message), status_code) {
+            (Some(error), _) => error,
+            (None, 404) => DockerError::ImageNotFound(image.to_string()),
+            (None, 401 | 403) => DockerError::RegistryAuth(image.to_string()),
+            (None, status) => DockerError::Response { status, message },
+        },
+        BollardError::DockerStreamError { error } => {
+            pull_message_error(image, &error).unwrap_or(DockerError::Stream(error))
+        }
+        e => e.into(),
+    }
+}
+
+// Errors reported inside the pull stream only carry a message. Docker Hub's "pull access
+// denied" is also what a repository that doesn't exist gets, so it counts as not found
+fn pull_message_error(image: &str, message: &str) -> Option<DockerError> {
+    let message = message.to_lowercase();
+    let mentions = |patterns: &[&str]| patterns.iter().any(|pattern| message.contains(pattern));
+    if mentions(&["not found", "manifest unknown", "pull access denied"]) {
+        Some(DockerError::ImageNotFound(image.to_string()))
+    } else if mentions(&["unauthorized", "authentication required", "denied"]) {
+        Some(DockerError::RegistryAuth(image.to_string()))
+    } else {
+        None
+    }
+}
+
+// Timeouts, dropped connections and gateway errors are worth retrying, anything
+// else (e.g. a missing image or a name conflict) fails the same way again
+pub fn is_transient(error: &anyhow::Error) -> bool {
+    match error.downcast_ref::<DockerError>() {
+        Some(DockerError::Timeout(_) | DockerError::Unavailable(_)) => true,
+        Some(DockerError::Response { status, .. }) => matches!(status, 502..=504),
+        _ => false,
+    }
+}
+
+// Stopping or removing a container Docker no longer knows leaves nothing to clean up
+pub fn is_container_gone(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<DockerError>(),
+        Some(DockerError::ContainerNotFound(_))
+    )
+}
+
+#[derive(Debug, Clone)]
+pub struct ContainerState {
+    pub status: String,
+    pub exit_code: Option<i64>,
+    pub started_at: Option<DateTime<Utc>>,
+    /// Healthcheck status, None when the image defines no HEALTHCHECK
+    pub health: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExistingContainer {
+    pub id: String,
+    pub image: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub state: ContainerState,
+}
+
+const DOCKER_TIMEOUT_SECS: u64 = 120;
+
+fn is_remote_host(host: &str) -> bool {
+    ["tcp://", "http://", "https://"]
+        .iter()
+        .any(|scheme| host.starts_with(scheme))
+}
+
+/// Connection state published to `/v1/ready`
+#[derive(Debug, Clone, Default)]
+pub struct DockerHealth {
+    /// False from the first connection error until a reconnect succeeds
+    pub connected: bool,
+    /// Version from the last successful handshake
+    pub version: Option<String>,
+    pub error: Option<String>,
+    /// Reconnect attempts that failed since the connection was lost
+    pub failed_reconnects: u32,
+}
+
+// The first handshake only decides the initial health, a hanging daemon must not hold up
+// the API. Later checks run in the processor's background retries with the full timeout.
+const STARTUP_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+const RECONNECT_MIN: Duration = Duration::from_secs(1);
+const RECONNECT_MAX: Duration = Duration::from_secs(30);
+
+// What's needed to build a fresh client after the daemon went away
+struct ConnectSettings {
+    host: Option<String>,
+    tls_cert_path: Option<String>,
+}
+
+impl ConnectSettings {
+    fn connect(&self) -> Result<Docker, BollardError> {
+        match self.host.as_deref() {
+            Some(host) if is_remote_host(host) => match self.tls_cert_path.as_deref() {
+                Some(cert_path) => {
+                    info!(
+                        host,
+                        cert_path, "Connecting to remote Docker daemon over TLS"
+                    );
+                    let cert_path = Path::new(cert_path);
+                    Docker::connect_with_ssl(
+                        host,
+                        &cert_path.join("key.pem"),
+                        &cert_path.join("cert.pem"),
+                        &cert_path.join("ca.pem"),
+                        DOCKER_TIMEOUT_SECS,
+                        API_DEFAULT_VERSION,
+                    )
+                }
+                None => {
+                    info!(host, "Connecting to remote Docker daemon over HTTP");
+                    Docker::connect_with_http(host, DOCKER_TIMEOUT_SECS, API_DEFAULT_VERSION)
+                }
+            },
+            _ => Docker::connect_with_local_defaults(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct ConnectionState {
+    health: DockerHealth,
+    // Earliest time for the next reconnect, None while connected
+    retry_at: Option<Instant>,
+}
+
+impl ConnectionState {
+    fn lost(&mut self, error: String) {
+        self.health.connected = false;
+        self.health.error = Some(error);
+        self.retry_at = Some(Instant::now() + self.backoff());
+    }
+
+    fn backoff(&self) -> Duration {
+        RECONNECT_MIN
+            .saturating_mul(1 << self.health.failed_reconnects.min(5))
+            .min(RECONNECT_MAX)
+    }
+
+    fn reconnect_due(&self) -> bool {
+        self.retry_at
+            .is_some_and(|retry_at| Instant::now() >= retry_at)
+    }
+}
+
+struct Connection {
+    settings: ConnectSettings,
+    docker: RwLock<Docker>,
+    state: Mutex<ConnectionState>,
+    reconnecting: tokio::sync::Mutex<()>,
+}
+
+impl Connection {
+    fn state(&self) -> MutexGuard<'_, ConnectionState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+// Expiry is reported like bollard's own request timeout, which callers already retry
+async fn bounded<T>(
+    timeout: Duration,
+    call: impl Future<Output = Result<T, BollardError>>,
+) -> Result<T, BollardError> {
+    tokio::time::timeout(timeout, call)
+        .await
+        .unwrap_or(Err(BollardError::RequestTimeoutError))
+}
+
+// Stream counterpart of bounded, for calls whose response is read as a stream
+async fn next_before<T>(
+    deadline: Instant,
+    stream: &mut (impl Stream<Item = Result<T, BollardError>> + Unpin),
+) -> Option<Result<T, BollardError>> {
+    tokio::time::timeout_at(deadline.into(), stream.next())
+        .await
+        .unwrap_or(Some(Err(BollardError::RequestTimeoutError)))
+}
+
+// Errors where the request never got a response from the daemon
+fn is_connection_error(error: &BollardError) -> bool {
+    matches!(
+        error,
+        BollardError::HyperResponseError { .. } | BollardError::IOError { .. }
+    )
+}
 
 #[derive(Clone)]
 pub struct DockerService {
-    _docker: Docker,
+    connection: Arc<Connection>,
+    pull_timeout: Duration,
+    call_timeout: Duration,
+    registries: HashMap<String, RegistryCredentials>,
 }
 
 impl DockerService {
-    #[tracing::instrument]
-    pub async fn new() -> Result<Self> {
-        let docker = Docker::connect_with_local_defaults()?;
-        let version = docker.version().await?;
-        info!(
-            version = version.version,
-            "Docker service initialized successfully"
+    #[tracing::instrument(skip(config))]
+    pub async fn new(config: &AppConfig) -> Result<Self> {
+        let settings = ConnectSettings {
+            host: config.docker_host.clone(),
+            tls_cert_path: config.docker_tls_cert_path.clone(),
+        };
+        let docker = settings.connect()?;
+        let call_timeout = Duration::from_secs(config.docker_call_timeout_secs.max(1));
+
+        // The processor keeps retrying, so an unreachable daemon doesn't stop startup
+        let mut state = ConnectionState::default();
+        let startup_timeout = call_timeout.min(STARTUP_CHECK_TIMEOUT);
+        match bounded(startup_timeout, docker.version()).await {
+            Ok(version) => {
+                info!(
+                    version = version.version,
+                    "Docker service initialized successfully"
+                );
+                state.health.connected = true;
+                state.health.version = version.version;
+            }
+            Err(e) => {
+                warn!("Docker daemon is not reachable yet: {}", e);
+                state.lost(e.to_string());
+            }
+        }
+        Ok(Self {
+            connection: Arc::new(Connection {
+                settings,
+                docker: RwLock::new(docker),
+                state: Mutex::new(state),
+                reconnecting: tokio::sync::Mutex::new(()),
+            }),
+            pull_timeout: Duration::from_secs(config.pull_timeout_secs),
+            call_timeout,
+            registries: config.registries.clone(),
+        })
+    }
+
+    pub fn health(&self) -> DockerHealth {
+        self.connection.state().health.clone()
+    }
+
+    // Hands out the current client, first reconnecting when the connection was lost and
+    // the backoff has passed. Calls made meanwhile keep using the old client.
+    async fn client(&self) -> Docker {
+        if self.connection.state().reconnect_due() {
+            self.reconnect().await;
+        }
+        self.current_client()
+    }
+
+    fn current_client(&self) -> Docker {
+        match self.connection.docker.read() {
+            Ok(docker) => docker.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    async fn reconnect(&self) {
+        // Concurrent callers don't pile up behind a slow handshake
+        let Ok(_guard) = self.connection.reconnecting.try_lock() else {
+            return;
+        };
+        if !self.connection.state().reconnect_due() {
+            return;
+        }
+
+        info!("Reconnecting to the Docker daemon");
+        let result = match self.connection.settings.connect() {
+            Ok(docker) => bounded(self.call_timeout, docker.version())
+                .await
+                .map(|version| (docker, version)),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok((docker, version)) => {
+                info!(
+                    version = version.version,
+                    "Reconnected to the Docker daemon"
+                );
+                match self.connection.docker.write() {
+                    Ok(mut current) => *current = docker,
+                    Err(poisoned) => *poisoned.into_inner() = docker,
+                }
+                let mut state = self.connection.state();
+                state.health = DockerHealth {
+                    connected: true,
+                    version: version.version,
+                    ..Default::default()
+                };
+                state.retry_at = None;
+            }
+            Err(e) => {
+                let mut state = self.connection.state();
+                state.health.failed_reconnects += 1;
+                state.lost(e.to_string());
+                warn!(
+                    "Failed to reconnect to the Docker daemon, retrying in {}s: {}",
+                    state.backoff().as_secs(),
+                    e
+                );
+            }
+        }
+    }
+
+    // Every call is bounded so a wedged daemon can't hold up the caller, e.g. the processor
+    // loop, indefinitely. Pulls and followed streams are long-running and not covered.
+    async fn call<T>(
+        &self,
+        call: impl Future<Output = Result<T, BollardError>>,
+    ) -> Result<T, BollardError> {
+        self.call_within(self.call_timeout, call).await
+    }
+
+    async fn call_within<T>(
+        &self,
+        timeout: Duration,
+        call: impl Future<Output = Result<T, BollardError>>,
+    ) -> Result<T, BollardError> {
+        self.observe(bounded(timeout, call).await)
+    }
+
+    // Connection failures mark the client as lost so the next call reconnects, other
+    // errors (e.g. a 404) say nothing about the connection
+    fn observe<T>(&self, result: Result<T, BollardError>) -> Result<T, BollardError> {
+        match &result {
+            Ok(_) => {
+                let mut state = self.connection.state();
+                if !state.health.connected {
+                    state.health.connected = true;
+                    state.health.error = None;
+                    state.health.failed_reconnects = 0;
+                    state.retry_at = None;
+                }
+            }
+            Err(e) => self.note_error(e),
+        }
+        result
+    }
+
+    fn note_error(&self, error: &BollardError) {
+        if is_connection_error(error) {
+            let mut state = self.connection.state();
+            if state.health.connected {
+                warn!("Lost connection to the Docker daemon: {}", error);
+            }
+            state.lost(error.to_string());
+        }
+    }
+
+    pub async fn version(&self) -> Result<String> {
+        let version = self
+            .call(self.client().await.version())
+            .await
+            .map_err(DockerError::from)?;
+        let version = version.version.unwrap_or_default();
+        self.connection.state().health.version = Some(version.clone());
+        Ok(version)
+    }
+
+    pub async fn image_exists(&self, image: &str) -> Result<bool> {
+        match self.call(self.client().await.inspect_image(image)).await {
+            Ok(_) => Ok(true),
+            Err(BollardError::DockerResponseServerError {
+                status_code: 404, ..
+            }) => Ok(false),
+            Err(e) => {
+                error!("Failed to inspect image: {}", e);
+                Err(DockerError::from(e).into())
+            }
+        }
+    }
+
+    // First RepoDigests entry, None for images that never came from a registry
+    pub async fn image_digest(&self, image: &str) -> Result<Option<String>> {
+        match self.call(self.client().await.inspect_image(image)).await {
+            Ok(info) => Ok(info
+                .repo_digests
+                .and_then(|digests| digests.into_iter().next())),
+            Err(e) => {
+                error!("Failed to inspect image: {}", e);
+                Err(DockerError::from(e).into())
+            }
+        }
+    }
+
+    // Dangling images are the untagged ones nothing refers to anymore, e.g. left by a re-pull
+    pub async fn list_images(&self, dangling: bool) -> Result<Vec<ImageResponse>> {
+        let mut filters = HashMap::new();
+        if dangling {
+            filters.insert("dangling", vec!["true"]);
+        }
+        let options = Some(ListImagesOptions {
+            filters,
+            ..Default::default()
+        });
+        let images = match self.call(self.client().await.list_images(options)).await {
+            Ok(images) => images,
+            Err(e) => {
+                error!("Failed to list images: {}", e);
+                return Err(DockerError::from(e).into());
+            }
+        };
+        Ok(images
+            .into_iter()
+            .map(|image| ImageResponse {
+                id: image.id,
+                // Older daemons report "<none>:<none>" for untagged images
+                repo_tags: image
+                    .repo_tags
+                    .into_iter()
+                    .filter(|tag| tag != "<none>:<none>")
+                    .collect(),
+                size_bytes: image.size.max(0) as u64,
+                created_at: DateTime::from_timestamp(image.created, 0),
+            })
+            .collect())
+    }
+
+    pub async fn network_exists(&self, network: &str) -> Result<bool> {
+        let result = self
+            .call(
+                self.client()
+                    .await
+                    .inspect_network(network, None::<InspectNetworkOptions<String>>),
+            )
+            .await;
+        match result {
+            Ok(_) => Ok(true),
+            Err(BollardError::DockerResponseServerError {
+                status_code: 404, ..
+            }) => Ok(false),
+            Err(e) => {
+                error!("Failed to inspect network: {}", e);
+                Err(DockerError::from(e).into())
+            }
+        }
+    }
+
+    pub async fn create_network(&self, name: &str, driver: Option<&str>) -> Result<String> {
+        info!("Creating network: {}", name);
+        let options = CreateNetworkOptions {
+            name,
+            driver: driver.unwrap_or("bridge"),
+            check_duplicate: true,
+            ..Default::default()
+        };
+        match self.call(self.client().await.create_network(options)).await {
+            Ok(response) => {
+                let network_id = response.id.unwrap_or_default();
+                info!("Network created successfully: {}", network_id);
+                Ok(network_id)
+            }
+            Err(BollardError::DockerResponseServerError {
+                status_code: 409, ..
+            }) => {
+                error!("Network name already in use: {}", name);
+                Err(DockerError::NetworkNameConflict(name.to_string()).into())
+            }
+            Err(e) => {
+                error!("Failed to create network: {}", e);
+                Err(DockerError::from(e).into())
+            }
+        }
+    }
+
+    // A network that's already gone counts as removed
+    pub async fn remove_network(&self, network_id: &str) -> Result<()> {
+        info!("Removing network: {}", network_id);
+        match self
+            .call(self.client().await.remove_network(network_id))
+            .await
+        {
+            Ok(_)
+            | Err(BollardError::DockerResponseServerError {
+                status_code: 404, ..
+            }) => {
+                info!("Network removed successfully: {}", network_id);
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to remove network: {}", e);
+                Err(DockerError::from(e).into())
+            }
+        }
+    }
+
+    // Pulls without a time limit, DockerApi::pull_image bounds it with the pull timeout
+    pub async fn fetch_image(&self, image: &str) -> Result<()> {
+        info!("Pulling image: {}", image);
+        let (from_image, tag) = split_image_reference(image);
+        let options = Some(CreateImageOptions {
+            from_image,
+            tag,
+            ..Default::default()
+        });
+        let credentials = self.registry_credentials(image);
+
+        let docker = self.client().await;
+        let mut stream = docker.create_image(options, None, credentials);
+        while let Some(progress) = stream.next().await {
+            match progress {
+                Ok(info) => {
+                    if let Some(message) = info.error {
+                        error!("Failed to pull image {}: {}", image, message);
+                        if let Some(error) = pull_message_error(image, &message) {
+                            return Err(error.into());
+                        }
+                        return Err(anyhow::anyhow!(
+                            "Failed to pull image {}: {}",
+                            image,
+                            message
+                        ));
+                    }
+                    debug!(
+                        status = info.status,
+                        progress = info.progress,
+                        "Pulling image {}",
+                        image
+                    );
+                }
+                Err(e) => {
+                    self.note_error(&e);
+                    let error = pull_error(image, e);
+                    error!("Failed to pull image {}: {}", image, error);
+                    return Err(error.into());
+                }
+            }
+        }
+        info!("Image pulled successfully: {}", image);
+        Ok(())
+    }
+
+    fn registry_credentials(&self, image: &str) -> Option<DockerCredentials> {
+        let host = registry_host(image);
+        let credentials = self.registries.get(host)?;
+        debug!(
+            "Pulling image {} with credentials for registry {}",
+            image, host
         );
-        Ok(Self { _docker: docker })
+        Some(DockerCredentials {
+            username: credentials.username.clone(),
+            password: credentials.password.clone(),
+            registrytoken: credentials.token.clone(),
+            serveraddress: Some(host.to_string()),
+            ..Default::default()
+        })
     }
 
     pub async fn create_container(&self, request: &CreateContainerRequest) -> Result<String> {
         info!("Creating container: {}", request.name);
 
+        if !self.image_exists(&request.image).await? {
+            self.pull_image(&request.image).await?;
+        }
+
         let options = Some(CreateContainerOptions {
             name: &request.name,
             platform: None,
         });
-        let config = Config {
-            image: Some(request.image.clone()),
-            ..Default::default()
-        };
+        let config = container_config(request);
 
-        let container_id = match self._docker.create_container(options, config).await {
+        let container_id = match self
+            .call(self.client().await.create_container(options, config))
+            .await
+        {
             Ok(response) => response.id,
+            Err(BollardError::DockerResponseServerError {
+                status_code: 409, ..
+            }) => {
+                error!("Container name already in use: {}", request.name);
+                return Err(DockerError::NameConflict(request.name.clone()).into());
+            }
             Err(e) => {
                 error!("Failed to create container: {}", e);
-                return Err(e.into());
+                return Err(DockerError::from(e).into());
             }
         };
         info!("Container created successfully: {}", container_id);
@@ -53,26 +682,93 @@ impl DockerService {
         let options = Some(StartContainerOptions::<&str> {
             ..Default::default()
         });
-        match self._docker.start_container(container_name, options).await {
+        match self
+            .call(self.client().await.start_container(container_name, options))
+            .await
+        {
             Ok(_) => info!("Container started successfully: {}", container_name),
             Err(e) => {
                 error!("Failed to start container: {}", e);
-                return Err(e.into());
+                return Err(container_error(container_name, e).into());
             }
         };
         Ok(())
     }
 
-    pub async fn stop_container(&self, container_name: &str) -> Result<()> {
+    pub async fn stop_container(&self, container_name: &str, timeout_secs: u64) -> Result<()> {
         info!("Stopping container: {}", container_name);
         let options = Some(StopContainerOptions {
-            t: 30, // Timeout in seconds
+            t: timeout_secs as i64, // Grace period before Docker kills the container
         });
-        match self._docker.stop_container(container_name, options).await {
+        // Docker only answers once the grace period is over, so it counts on top of the timeout
+        let call_timeout = self.call_timeout + Duration::from_secs(timeout_secs);
+        match self
+            .call_within(
+                call_timeout,
+                self.client().await.stop_container(container_name, options),
+            )
+            .await
+        {
             Ok(_) => info!("Container stopped successfully: {}", container_name),
             Err(e) => {
                 error!("Failed to stop container: {}", e);
-                return Err(e.into());
+                return Err(container_error(container_name, e).into());
+            }
+        };
+        Ok(())
+    }
+
+    pub async fn pause_container(&self, container_name: &str) -> Result<()> {
+        info!("Pausing container: {}", container_name);
+        match self
+            .call(self.client().await.pause_container(container_name))
+            .await
+        {
+            Ok(_) => info!("Container paused successfully: {}", container_name),
+            Err(e) => {
+                error!("Failed to pause container: {}", e);
+                return Err(container_error(container_name, e).into());
+            }
+        };
+        Ok(())
+    }
+
+    pub async fn unpause_container(&self, container_name: &str) -> Result<()> {
+        info!("Unpausing container: {}", container_name);
+        match self
+            .call(self.client().await.unpause_container(container_name))
+            .await
+        {
+            Ok(_) => info!("Container unpaused successfully: {}", container_name),
+            Err(e) => {
+                error!("Failed to unpause container: {}", e);
+                return Err(container_error(container_name, e).into());
+            }
+        };
+        Ok(())
+    }
+
+    pub async fn rename_container(&self, container_name: &str, name: &str) -> Result<()> {
+        info!("Renaming container {} to {}", container_name, name);
+        let options = RenameContainerOptions { name };
+        match self
+            .call(
+                self.client()
+                    .await
+                    .rename_container(container_name, options),
+            )
+            .await
+        {
+            Ok(_) => info!("Container renamed successfully: {}", container_name),
+            Err(BollardError::DockerResponseServerError {
+                status_code: 409, ..
+            }) => {
+                error!("Container name already in use: {}", name);
+                return Err(DockerError::NameConflict(name.to_string()).into());
+            }
+            Err(e) => {
+                error!("Failed to rename container: {}", e);
+                return Err(container_error(container_name, e).into());
             }
         };
         Ok(())
@@ -83,28 +779,302 @@ impl DockerService {
         let options = Some(RemoveContainerOptions {
             ..Default::default()
         });
-        match self._docker.remove_container(container_name, options).await {
+        match self
+            .call(
+                self.client()
+                    .await
+                    .remove_container(container_name, options),
+            )
+            .await
+        {
             Ok(_) => info!("Container removed successfully: {}", container_name),
             Err(e) => {
                 error!("Failed to remove container: {}", e);
-                return Err(e.into());
+                return Err(container_error(container_name, e).into());
             }
         };
         Ok(())
     }
 
-    pub async fn get_container_status(&self, _container_id: &str) -> Result<String> {
+    pub async fn inspect_container(&self, container_id: &str) -> Result<ContainerInspectResponse> {
+        let options = Some(InspectContainerOptions {
+            ..Default::default()
+        });
+        match self
+            .call(self.client().await.inspect_container(container_id, options))
+            .await
+        {
+            Ok(info) => Ok(info),
+            Err(e) => {
+                error!("Failed to inspect container: {}", e);
+                Err(container_error(container_id, e).into())
+            }
+        }
+    }
+
+    pub async fn get_container_state(&self, container_id: &str) -> Result<ContainerState> {
         let options = Some(InspectContainerOptions {
             ..Default::default()
         });
-        let container_status = match self._docker.inspect_container(_container_id, options).await {
-            Ok(info) => info.state.unwrap().status.unwrap().to_string(),
+        let container_state = match self
+            .call(self.client().await.inspect_container(container_id, options))
+            .await
+        {
+            Ok(info) => state_from_inspect(&info),
             Err(e) => {
                 error!("Failed to inspect container: {}", e);
-                return Err(e.into());
+                return Err(container_error(container_id, e).into());
             }
         };
-        Ok(container_status)
+        Ok(container_state)
+    }
+
+    /// Looks a container up by id or name, None when Docker doesn't know it
+    pub async fn find_container(&self, id_or_name: &str) -> Result<Option<ExistingContainer>> {
+        match self
+            .call(self.client().await.inspect_container(id_or_name, None))
+            .await
+        {
+            Ok(info) => Ok(Some(ExistingContainer {
+                id: info.id.clone().unwrap_or_else(|| id_or_name.to_string()),
+                image: info.config.as_ref().and_then(|config| config.image.clone()),
+                created_at: info
+                    .created
+                    .as_deref()
+                    .and_then(|created| DateTime::parse_from_rfc3339(created).ok())
+                    .map(|created| created.with_timezone(&Utc)),
+                state: state_from_inspect(&info),
+            })),
+            Err(BollardError::DockerResponseServerError {
+                status_code: 404, ..
+            }) => Ok(None),
+            Err(e) => {
+                error!("Failed to inspect container: {}", e);
+                Err(DockerError::from(e).into())
+            }
+        }
+    }
+
+    pub async fn get_container_logs(
+        &self,
+        container_id: &str,
+        tail: usize,
+        timestamps: bool,
+    ) -> Result<Vec<String>> {
+        let options = Some(LogsOptions::<String> {
+            stdout: true,
+            stderr: true,
+            timestamps,
+            tail: tail.to_string(),
+            ..Default::default()
+        });
+        let docker = self.client().await;
+        let mut stream = docker.logs(container_id, options);
+
+        let deadline = Instant::now() + self.call_timeout;
+        let mut lines = Vec::new();
+        while let Some(frame) = next_before(deadline, &mut stream).await {
+            match frame {
+                Ok(output) => lines.extend(output.to_string().lines().map(str::to_string)),
+                Err(e) => {
+                    error!("Failed to fetch container logs: {}", e);
+                    self.note_error(&e);
+                    return Err(container_error(container_id, e).into());
+                }
+            }
+        }
+        Ok(lines)
+    }
+
+    pub fn follow_container_logs(
+        &self,
+        container_id: &str,
+        tail: usize,
+        timestamps: bool,
+    ) -> impl Stream<Item = Result<String>> {
+        let options = LogsOptions::<String> {
+            follow: true,
+            stdout: true,
+            stderr: true,
+            timestamps,
+            tail: tail.to_string(),
+            ..Default::default()
+        };
+        self.follow_logs(container_id, options)
+    }
+
+    /// Follows the whole log from the unix timestamp on, 0 for all of it, with timestamps
+    pub fn follow_container_logs_since(
+        &self,
+        container_id: &str,
+        since: i64,
+    ) -> impl Stream<Item = Result<String>> {
+        let options = LogsOptions::<String> {
+            follow: true,
+            stdout: true,
+            stderr: true,
+            since,
+            timestamps: true,
+            tail: "all".to_string(),
+            ..Default::default()
+        };
+        self.follow_logs(container_id, options)
+    }
+
+    fn follow_logs(
+        &self,
+        container_id: &str,
+        options: LogsOptions<String>,
+    ) -> impl Stream<Item = Result<String>> {
+        // Stream ends when the container exits, since Docker closes the follow connection
+        let container = container_id.to_string();
+        self.current_client()
+            .logs(container_id, Some(options))
+            .flat_map(move |frame| {
+                let lines: Vec<Result<String>> = match frame {
+                    Ok(output) => output
+                        .to_string()
+                        .lines()
+                        .map(|line| Ok(line.replace('\r', "")))
+                        .collect(),
+                    Err(e) => {
+                        error!("Failed to follow container logs: {}", e);
+                        vec![Err(container_error(&container, e).into())]
+                    }
+                };
+                futures::stream::iter(lines)
+            })
+    }
+
+    // Yields the Docker id of every container that started, stopped or died
+    pub fn container_events(&self) -> impl Stream<Item = Result<String>> {
+        let mut filters = HashMap::new();
+        filters.insert("type".to_string(), vec!["container".to_string()]);
+        filters.insert(
+            "event".to_string(),
+            vec!["start".to_string(), "stop".to_string(), "die".to_string()],
+        );
+        let options = Some(EventsOptions::<String> {
+            filters,
+            ..Default::default()
+        });
+
+        // The processor resubscribes after errors, picking up a reconnected client
+        let service = self.clone();
+        self.current_client()
+            .events(options)
+            .filter_map(move |event| {
+                let service = service.clone();
+                async move {
+                    match event {
+                        Ok(message) => message.actor.and_then(|actor| actor.id).map(Ok),
+                        Err(e) => {
+                            error!("Failed to read Docker events: {}", e);
+                            service.note_error(&e);
+                            Some(Err(DockerError::from(e).into()))
+                        }
+                    }
+                }
+            })
+    }
+
+    pub async fn exec(
+        &self,
+        container_id: &str,
+        cmd: Vec<String>,
+        max_output_bytes: usize,
+    ) -> Result<ExecResponse> {
+        let options = CreateExecOptions {
+            attach_stdout: Some(true),
+            attach_stderr: Some(true),
+            cmd: Some(cmd),
+            ..Default::default()
+        };
+        let exec = self
+            .call(self.client().await.create_exec(container_id, options))
+            .await
+            .map_err(|e| container_error(container_id, e))?;
+
+        let mut output = Vec::new();
+        let mut truncated = false;
+        if let StartExecResults::Attached {
+            output: mut stream, ..
+        } = self
+            .call(self.client().await.start_exec(&exec.id, None))
+            .await
+            .map_err(DockerError::from)?
+        {
+            let deadline = Instant::now() + self.call_timeout;
+            while let Some(frame) = next_before(deadline, &mut stream).await {
+                let bytes = match frame {
+                    Ok(frame) => frame.into_bytes(),
+                    Err(e) => {
+                        error!("Failed to read exec output: {}", e);
+                        return Err(DockerError::from(e).into());
+                    }
+                };
+                let remaining = max_output_bytes - output.len();
+                if bytes.len() > remaining {
+                    // Dropping the stream detaches us, the command itself keeps running
+                    output.extend_from_slice(&bytes[..remaining]);
+                    truncated = true;
+                    break;
+                }
+                output.extend_from_slice(&bytes);
+            }
+        }
+
+        let inspect = self
+            .call(self.client().await.inspect_exec(&exec.id))
+            .await
+            .map_err(DockerError::from)?;
+        let exit_code = match inspect.running {
+            Some(true) => None,
+            _ => inspect.exit_code,
+        };
+
+        Ok(ExecResponse {
+            output: String::from_utf8_lossy(&output).into_owned(),
+            exit_code,
+            truncated,
+        })
+    }
+
+    pub async fn get_container_stats(&self, container_id: &str) -> Result<ContainerStats> {
+        let options = Some(StatsOptions {
+            stream: false,
+            one_shot: false, // Let Docker take two samples so precpu_stats is populated
+        });
+        let docker = self.client().await;
+        let mut stream = docker.stats(container_id, options);
+        let stats = match next_before(Instant::now() + self.call_timeout, &mut stream).await {
+            Some(Ok(stats)) => stats,
+            Some(Err(e)) => {
+                error!("Failed to fetch container stats: {}", e);
+                self.note_error(&e);
+                return Err(container_error(container_id, e).into());
+            }
+            None => return Err(anyhow::anyhow!("Docker returned no stats")),
+        };
+        Ok(container_stats_from(&stats))
+    }
+
+    pub async fn top(&self, container_id: &str) -> Result<ContainerTop> {
+        let options = None::<TopOptions<String>>;
+        let top = match self
+            .call(self.client().await.top_processes(container_id, options))
+            .await
+        {
+            Ok(top) => top,
+            Err(e) => {
+                error!("Failed to list container processes: {}", e);
+                return Err(container_error(container_id, e).into());
+            }
+        };
+        Ok(ContainerTop {
+            titles: top.titles.unwrap_or_default(),
+            processes: top.processes.unwrap_or_default(),
+        })
     }
 
     pub async fn _list_containers(&self) -> Result<Vec<String>> {
@@ -113,13 +1083,364 @@ impl DockerService {
             all: true,
             ..Default::default()
         });
-        let containers = match self._docker.list_containers(options).await {
+        let containers = match self
+            .call(self.client().await.list_containers(options))
+            .await
+        {
             Ok(containers) => containers.iter().filter_map(|c| c.id.clone()).collect(),
             Err(e) => {
                 error!("Failed to list containers: {}", e);
-                return Err(e.into());
+                return Err(DockerError::from(e).into());
             }
         };
         Ok(containers)
     }
 }
+
+/// The Docker calls the processor reconciles containers with. DockerService is the real
+/// implementation, tests drive the processor through a fake one instead.
+#[async_trait]
+pub trait DockerApi: Send + Sync {
+    async fn version(&self) -> Result<String>;
+    async fn image_exists(&self, image: &str) -> Result<bool>;
+    async fn image_digest(&self, image: &str) -> Result<Option<String>>;
+    async fn create_container(&self, request: &CreateContainerRequest) -> Result<String>;
+    async fn start_container(&self, container_name: &str) -> Result<()>;
+    async fn stop_container(&self, container_name: &str, timeout_secs: u64) -> Result<()>;
+    async fn remove_container(&self, container_name: &str) -> Result<()>;
+    async fn get_container_state(&self, container_id: &str) -> Result<ContainerState>;
+    async fn find_container(&self, id_or_name: &str) -> Result<Option<ExistingContainer>>;
+    async fn fetch_image(&self, image: &str) -> Result<()>;
+    fn pull_timeout(&self) -> Duration;
+    fn container_events(&self) -> BoxStream<'static, Result<String>>;
+    fn follow_container_logs_since(
+        &self,
+        container_id: &str,
+        since: i64,
+    ) -> BoxStream<'static, Result<String>>;
+
+    async fn get_container_status(&self, container_id: &str) -> Result<String> {
+        Ok(self.get_container_state(container_id).await?.status)
+    }
+
+    // Like get_container_state, but a container Docker no longer knows about is None
+    async fn find_container_state(&self, container_id: &str) -> Result<Option<ContainerState>> {
+        Ok(self
+            .find_container(container_id)
+            .await?
+            .map(|container| container.state))
+    }
+
+    async fn pull_image(&self, image: &str) -> Result<()> {
+        // A stalled registry would otherwise keep the pull open forever
+        let pull_timeout = self.pull_timeout();
+        match tokio::time::timeout(pull_timeout, self.fetch_image(image)).await {
+            Ok(result) => result,
+            Err(_) => {
+                error!(
+                    "Pulling image {} timed out after {}s",
+                    image,
+                    pull_timeout.as_secs()
+                );
+                Err(DockerError::Timeout(format!(
+                    "pulling image {} took longer than {}s",
+                    image,
+                    pull_timeout.as_secs()
+                ))
+                .into())
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl DockerApi for DockerService {
+    async fn version(&self) -> Result<String> {
+        DockerService::version(self).await
+    }
+
+    async fn image_exists(&self, image: &str) -> Result<bool> {
+        DockerService::image_exists(self, image).await
+    }
+
+    async fn image_digest(&self, image: &str) -> Result<Option<String>> {
+        DockerService::image_digest(self, image).await
+    }
+
+    async fn create_container(&self, request: &CreateContainerRequest) -> Result<String> {
+        DockerService::create_container(self, request).await
+    }
+
+    async fn start_container(&self, container_name: &str) -> Result<()> {
+        DockerService::start_container(self, container_name).await
+    }
+
+    async fn stop_container(&self, container_name: &str, timeout_secs: u64) -> Result<()> {
+        DockerService::stop_container(self, container_name, timeout_secs).await
+    }
+
+    async fn remove_container(&self, container_name: &str) -> Result<()> {
+        DockerService::remove_container(self, container_name).await
+    }
+
+    async fn get_container_state(&self, container_id: &str) -> Result<ContainerState> {
+        DockerService::get_container_state(self, container_id).await
+    }
+
+    async fn find_container(&self, id_or_name: &str) -> Result<Option<ExistingContainer>> {
+        DockerService::find_container(self, id_or_name).await
+    }
+
+    async fn fetch_image(&self, image: &str) -> Result<()> {
+        DockerService::fetch_image(self, image).await
+    }
+
+    fn pull_timeout(&self) -> Duration {
+        self.pull_timeout
+    }
+
+    fn container_events(&self) -> BoxStream<'static, Result<String>> {
+        DockerService::container_events(self).boxed()
+    }
+
+    fn follow_container_logs_since(
+        &self,
+        container_id: &str,
+        since: i64,
+    ) -> BoxStream<'static, Result<String>> {
+        DockerService::follow_container_logs_since(self, container_id, since).boxed()
+    }
+}
+
+fn container_config(request: &CreateContainerRequest) -> Config<String> {
+    let mut exposed_ports = HashMap::new();
+    let mut port_bindings = HashMap::new();
+    for port in request.ports.iter().flatten() {
+        let container_port = format!("{}/{}", port.container_port, port.protocol());
+        exposed_ports.insert(container_port.clone(), HashMap::new());
+        port_bindings
+            .entry(container_port)
+            .or_insert_with(|| Some(Vec::new()))
+            .get_or_insert_with(Vec::new)
+            .push(PortBinding {
+                host_ip: None,
+                host_port: Some(port.host_port.to_string()),
+            });
+    }
+
+    let host_config = HostConfig {
+        port_bindings: Some(port_bindings),
+        binds: request.volumes.clone(),
+        memory: request
+            .memory_mb
+            .map(|memory_mb| (memory_mb * 1024 * 1024) as i64),
+        cpu_shares: request.cpu_shares.map(|cpu_shares| cpu_shares as i64),
+        network_mode: request.network.clone(),
+        auto_remove: Some(request.auto_remove),
+        ..Default::default()
+    };
+    let networking_config = request.network.clone().map(|network| NetworkingConfig {
+        endpoints_config: HashMap::from([(network, EndpointSettings::default())]),
+    });
+    Config {
+        image: Some(request.image.clone()),
+        env: request.env.clone(),
+        // An empty command would override the image default with nothing
+        cmd: request
+            .command
+            .clone()
+            .filter(|command| !command.is_empty()),
+        entrypoint: request.entrypoint.clone(),
+        labels: request.labels.clone(),
+        exposed_ports: Some(exposed_ports),
+        healthcheck: request.healthcheck.as_ref().map(health_config),
+        host_config: Some(host_config),
+        networking_config,
+        ..Default::default()
+    }
+}
+
+// Docker takes healthcheck durations in nanoseconds, unset fields keep its defaults
+fn health_config(healthcheck: &HealthcheckSpec) -> HealthConfig {
+    let nanos = |secs: u64| Duration::from_secs(secs).as_nanos() as i64;
+    HealthConfig {
+        test: Some(healthcheck.test.clone()),
+        interval: healthcheck.interval_secs.map(nanos),
+        timeout: healthcheck.timeout_secs.map(nanos),
+        retries: healthcheck.retries.map(|retries| retries as i64),
+        ..Default::default()
+    }
+}
+
+pub const UNKNOWN_CONTAINER_STATUS: &str = "unknown";
+
+// Inspect can return partial data for containers in transitional states,
+// so a missing state is reported as unknown instead of failing
+fn state_from_inspect(info: &ContainerInspectResponse) -> ContainerState {
+    let state = info.state.as_ref();
+    let status = match state.and_then(|state| state.status) {
+        Some(ContainerStateStatusEnum::EMPTY) | None => UNKNOWN_CONTAINER_STATUS.to_string(),
+        Some(status) => status.to_string(),
+    };
+    // Docker reports "0001-01-01T00:00:00Z" for containers that never started
+    let started_at = state
+        .and_then(|state| state.started_at.as_deref())
+        .and_then(|started_at| DateTime::parse_from_rfc3339(started_at).ok())
+        .map(|started_at| started_at.with_timezone(&Utc))
+        .filter(|started_at| started_at.year() > 1);
+    let health = match state.and_then(|state| state.health.as_ref()?.status) {
+        Some(HealthStatusEnum::EMPTY | HealthStatusEnum::NONE) | None => None,
+        Some(health) => Some(health.to_string()),
+    };
+    ContainerState {
+        status,
+        exit_code: state.and_then(|state| state.exit_code),
+        started_at,
+        health,
+    }
+}
+
+// Same rule as the Docker CLI: the first path component names a registry only when
+// it looks like a host, everything else comes from Docker Hub
+fn registry_host(image: &str) -> &str {
+    match image.split_once('/') {
+        Some((first, _)) if first.contains(['.', ':']) || first == "localhost" => first,
+        _ => "docker.io",
+    }
+}
+
+// Splits an image reference into the repository and tag/digest parts the pull API expects.
+// Without an explicit tag Docker would pull every tag, so default to "latest".
+fn split_image_reference(image: &str) -> (&str, &str) {
+    if let Some((name, digest)) = image.split_once('@') {
+        return (name, digest);
+    }
+
+    let name_start = image.rfind('/').map(|index| index + 1).unwrap_or(0);
+    match image[name_start..].rfind(':') {
+        Some(index) => (
+            &image[..name_start + index],
+            &image[name_start + index + 1..],
+        ),
+        None => (image, "latest"),
+    }
+}
+
+// Same calculation as `docker stats`: CPU usage delta relative to the system delta,
+// and memory usage excluding the inactive page cache
+fn container_stats_from(stats: &Stats) -> ContainerStats {
+    let cpu_delta = stats
+        .cpu_stats
+        .cpu_usage
+        .total_usage
+        .saturating_sub(stats.precpu_stats.cpu_usage.total_usage) as f64;
+    let system_delta = stats
+        .cpu_stats
+        .system_cpu_usage
+        .unwrap_or(0)
+        .saturating_sub(stats.precpu_stats.system_cpu_usage.unwrap_or(0))
+        as f64;
+    let online_cpus = stats.cpu_stats.online_cpus.unwrap_or_else(|| {
+        stats
+            .cpu_stats
+            .cpu_usage
+            .percpu_usage
+            .as_ref()
+            .map(|usage| usage.len() as u64)
+            .unwrap_or(1)
+    }) as f64;
+
+    let cpu_percent = if cpu_delta > 0.0 && system_delta > 0.0 {
+        (cpu_delta / system_delta) * online_cpus * 100.0
+    } else {
+        0.0
+    };
+
+    let inactive_file = match stats.memory_stats.stats {
+        Some(MemoryStatsStats::V1(v1)) => v1.total_inactive_file,
+        Some(MemoryStatsStats::V2(v2)) => v2.inactive_file,
+        None => 0,
+    };
+    let memory_usage_bytes = stats
+        .memory_stats
+        .usage
+        .unwrap_or(0)
+        .saturating_sub(inactive_file);
+    let memory_limit_bytes = stats.memory_stats.limit.unwrap_or(0);
+
+    let memory_percent = if memory_limit_bytes > 0 {
+        memory_usage_bytes as f64 / memory_limit_bytes as f64 * 100.0
+    } else {
+        0.0
+    };
+
+    ContainerStats {
+        cpu_percent,
+        memory_usage_bytes,
+        memory_limit_bytes,
+        memory_percent,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bollard::models::ContainerState as DockerContainerState;
+
+    #[test]
+    fn inspect_without_state_is_unknown() {
+        let info = ContainerInspectResponse {
+            state: None,
+            ..Default::default()
+        };
+        let state = state_from_inspect(&info);
+        assert_eq!(state.status, UNKNOWN_CONTAINER_STATUS);
+        assert_eq!(state.exit_code, None);
+        assert_eq!(state.started_at, None);
+        assert_eq!(state.health, None);
+    }
+
+    #[test]
+    fn inspect_state_is_read() {
+        let info = ContainerInspectResponse {
+            state: Some(DockerContainerState {
+                status: Some(ContainerStateStatusEnum::EXITED),
+                exit_code: Some(3),
+                started_at: Some("2026-10-15T10:00:00.5Z".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let state = state_from_inspect(&info);
+        assert_eq!(state.status, "exited");
+        assert_eq!(state.exit_code, Some(3));
+        assert!(state.started_at.is_some());
+    }
+
+    #[test]
+    fn command_is_passed_to_the_create_config() {
+        let request: CreateContainerRequest = serde_json::from_value(serde_json::json!({
+            "name": "sleeper",
+            "image": "busybox",
+            "command": ["sleep", "3600"]
+        }))
+        .unwrap();
+        let config = container_config(&request);
+        assert_eq!(
+            config.cmd,
+            Some(vec!["sleep".to_string(), "3600".to_string()])
+        );
+        assert_eq!(config.entrypoint, None);
+    }
+
+    #[test]
+    fn empty_command_keeps_the_image_default() {
+        let request: CreateContainerRequest = serde_json::from_value(serde_json::json!({
+            "name": "sleeper",
+            "image": "busybox",
+            "command": []
+        }))
+        .unwrap();
+        assert_eq!(container_config(&request).cmd, None);
+    }
+}
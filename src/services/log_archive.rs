@@ -0,0 +1,173 @@
+use anyhow::Result;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use std::collections::HashSet;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, info, warn};
+
+use crate::config::Config;
+use crate::services::docker::DockerApi;
+
+/// Copies container logs to `<dir>/<container id>.log` while they run, so they can still be
+/// read once Docker removed the container. A file reaching the size cap is moved to
+/// `<container id>.log.1`, replacing the one rotated before it.
+#[derive(Clone)]
+pub struct LogArchive {
+    dir: PathBuf,
+    max_bytes: u64,
+    // Containers with a capture running, there is at most one per container
+    capturing: Arc<Mutex<HashSet<String>>>,
+}
+
+// Releases the container's capture slot when the capture task ends
+struct CaptureGuard {
+    capturing: Arc<Mutex<HashSet<String>>>,
+    container_id: String,
+}
+
+impl Drop for CaptureGuard {
+    fn drop(&mut self) {
+        if let Ok(mut capturing) = self.capturing.lock() {
+            capturing.remove(&self.container_id);
+        }
+    }
+}
+
+impl LogArchive {
+    /// None unless container_log_dir is configured
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let dir = config.container_log_dir.as_ref()?;
+        info!(
+            "Archiving container logs to {} (rotated at {} bytes)",
+            dir, config.container_log_max_bytes
+        );
+        Some(Self {
+            dir: PathBuf::from(dir),
+            max_bytes: config.container_log_max_bytes.max(1),
+            capturing: Arc::new(Mutex::new(HashSet::new())),
+        })
+    }
+
+    fn path(&self, container_id: &str) -> PathBuf {
+        self.dir.join(format!("{}.log", container_id))
+    }
+
+    fn rotated_path(&self, container_id: &str) -> PathBuf {
+        self.dir.join(format!("{}.log.1", container_id))
+    }
+
+    /// Starts copying the container's logs unless a capture is already running. Docker ends
+    /// the stream when the container exits, a later start resumes where the file left off.
+    pub fn capture(&self, docker: &dyn DockerApi, container_id: &str, docker_id: &str) {
+        let guard = {
+            let Ok(mut capturing) = self.capturing.lock() else {
+                return;
+            };
+            if !capturing.insert(container_id.to_string()) {
+                return;
+            }
+            CaptureGuard {
+                capturing: self.capturing.clone(),
+                container_id: container_id.to_string(),
+            }
+        };
+
+        // Docker's since only has second precision, lines from the second the file was last
+        // written may show up twice rather than not at all
+        let since = std::fs::metadata(self.path(container_id))
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |since| since.as_secs() as i64);
+        let lines = docker.follow_container_logs_since(docker_id, since);
+
+        debug!(
+            "Capturing logs of container {} since {}",
+            container_id, since
+        );
+        let archive = self.clone();
+        tokio::spawn(async move {
+            let container_id = guard.container_id.clone();
+            if let Err(e) = archive.write_lines(&container_id, lines).await {
+                warn!(
+                    "Failed to archive logs of container {}: {:#}",
+                    container_id, e
+                );
+            }
+            drop(guard);
+        });
+    }
+
+    async fn write_lines(
+        &self,
+        container_id: &str,
+        mut lines: BoxStream<'static, Result<String>>,
+    ) -> Result<()> {
+        fs::create_dir_all(&self.dir).await?;
+        let path = self.path(container_id);
+        let mut file = open_append(&path).await?;
+        let mut size = file.metadata().await?.len();
+
+        while let Some(line) = lines.next().await {
+            let line = line? + "\n";
+            let len = line.len() as u64;
+            if size > 0 && size + len > self.max_bytes {
+                file.flush().await?;
+                fs::rename(&path, self.rotated_path(container_id)).await?;
+                file = open_append(&path).await?;
+                size = 0;
+            }
+            file.write_all(line.as_bytes()).await?;
+            size += len;
+        }
+        file.flush().await?;
+        Ok(())
+    }
+
+    /// Last `tail` archived lines of the container, None when nothing was archived for it.
+    /// Lines are stored with Docker's timestamps, which are cut off unless requested.
+    pub async fn read(
+        &self,
+        container_id: &str,
+        tail: usize,
+        timestamps: bool,
+    ) -> Result<Option<Vec<String>>> {
+        let mut contents = None::<String>;
+        for path in [self.rotated_path(container_id), self.path(container_id)] {
+            match fs::read_to_string(&path).await {
+                Ok(text) => contents.get_or_insert_with(String::new).push_str(&text),
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        let Some(contents) = contents else {
+            return Ok(None);
+        };
+
+        let lines: Vec<&str> = contents.lines().collect();
+        let lines = lines[lines.len().saturating_sub(tail)..]
+            .iter()
+            .map(|line| match timestamps {
+                true => line.to_string(),
+                false => line
+                    .split_once(' ')
+                    .map_or(*line, |(_, message)| message)
+                    .to_string(),
+            })
+            .collect();
+        Ok(Some(lines))
+    }
+}
+
+async fn open_append(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+}
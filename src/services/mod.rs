@@ -1,6 +1,7 @@
+pub mod docker;
+pub mod log_archive;
 pub mod processor;
 
-// Make docker module private to services, only accessible by processor
-mod docker;
-
+pub use docker::*;
+pub use log_archive::*;
 pub use processor::*;
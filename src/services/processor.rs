@@ -1,100 +1,888 @@
-use anyhow::Result;
-use chrono::Utc;
-use sea_orm::{ActiveModelTrait, EntityTrait, Set};
-use std::sync::{Arc, Mutex};
-use tokio::time::Duration;
-use tracing::{error, info, warn};
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use sea_orm::{
+    sea_query::{Expr, OnConflict, Query},
+    ActiveValue::Set,
+    ColumnTrait, Condition, EntityTrait, QueryFilter, QuerySelect, TransactionTrait,
+};
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::Mutex;
+use tokio::sync::{broadcast, mpsc, oneshot, watch};
+use tokio::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, info_span, warn, Instrument, Span};
 
+use crate::config::Config;
+use crate::models::v1::audit_log;
 use crate::models::v1::container::{
-    ActiveModel as ContainerActiveModel, Entity as ContainerEntity, Model as ContainerModel,
+    Column as ContainerColumn, ContainerStatus, ContainerStatusUpdate, CreateContainerRequest,
+    Entity as ContainerEntity, Model as ContainerModel,
+};
+use crate::models::v1::processor::{
+    ActiveModel as HeartbeatActiveModel, Column as HeartbeatColumn, Entity as HeartbeatEntity,
 };
-use crate::services::docker::DockerService;
+use crate::services::docker::{
+    is_container_gone, is_transient, DockerApi, DockerError, UNKNOWN_CONTAINER_STATUS,
+};
+use crate::services::log_archive::LogArchive;
 
 pub struct ProcessorService {
     db: sea_orm::DatabaseConnection,
-    docker: DockerService,
-    shutdown_signal: Arc<Mutex<bool>>,
+    // Recorded as the owner of the containers this processor reconciles
+    name: String,
+    owner_lease: Duration,
+    started_at: DateTime<Utc>,
+    docker: Box<dyn DockerApi>,
+    interval: Duration,
+    max_restart_retries: u32,
+    default_stop_timeout_secs: u64,
+    shutdown_drain_timeout: Duration,
+    stuck_timeout: Duration,
+    adopt_existing_containers: bool,
+    // Copies logs of running containers to disk when CONTAINER_LOG_DIR is set
+    log_archive: Option<LogArchive>,
+    docker_retry_attempts: u32,
+    docker_retry_base_delay: Duration,
+    shutdown: CancellationToken,
+    concurrency: usize,
+    // Containers currently being reconciled, shared by the poll and event loops
+    in_flight: Mutex<HashSet<String>>,
+    readiness: watch::Sender<Readiness>,
+    // Status changes for WebSocket watchers, sending fails harmlessly without any
+    status_updates: broadcast::Sender<ContainerStatusUpdate>,
+    reconcile_requests: mpsc::Sender<ReconcileRequest>,
+    // Taken by the reconcile loop for as long as it runs
+    reconcile_receiver: tokio::sync::Mutex<mpsc::Receiver<ReconcileRequest>>,
+}
+
+/// Asks the processor to reconcile one container right away instead of on the next tick
+pub struct ReconcileRequest {
+    pub container_id: String,
+    /// Receives the outcome once reconciliation finished, dropped unanswered on shutdown
+    pub done: oneshot::Sender<Result<()>>,
+}
+
+// Columns a status write can record alongside the status itself
+#[derive(Default)]
+struct StatusDetails {
+    attempts: Option<u32>,
+    // Some when the container exited, holding the code Docker reported if any
+    exit_code: Option<Option<i64>>,
+    image_digest: Option<String>,
+    // Docker's StartedAt when the write starts the container, defaults to now
+    started_at: Option<DateTime<Utc>>,
+    // Docker's Created time when the write records a new Docker container
+    docker_created_at: Option<DateTime<Utc>>,
+    // Spec the new Docker container was created from
+    spec_hash: Option<String>,
+    restart_count: Option<i64>,
 }
 
+// Everything logged while working on a container carries its id, name and image, so
+// JSON logs can be filtered on them. Transitions add from_status and to_status
+fn container_span(container: &ContainerModel) -> Span {
+    info_span!(
+        "container",
+        container.id = %container.id,
+        container.name = %container.name,
+        container.image = %container.image,
+    )
+}
+
+/// Processor state published to `/v1/ready`
+#[derive(Debug, Clone, Default)]
+pub struct Readiness {
+    /// Set once startup reconciliation has run and the loops are processing containers
+    pub initialized: bool,
+    /// Version reported by the last successful Docker check, None while unreachable
+    pub docker_version: Option<String>,
+    pub docker_error: Option<String>,
+    /// When the poll loop last finished a tick, or started if none has finished yet
+    pub last_tick: Option<Instant>,
+}
+
+// Releases the container's in-flight claim when reconciliation finishes or fails
+struct InFlightGuard<'a> {
+    in_flight: &'a Mutex<HashSet<String>>,
+    container_id: String,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut in_flight) = self.in_flight.lock() {
+            in_flight.remove(&self.container_id);
+        }
+    }
+}
+
+const EVENTS_RECONNECT_MIN: Duration = Duration::from_secs(1);
+const EVENTS_RECONNECT_MAX: Duration = Duration::from_secs(30);
+const DOCKER_WAIT_MAX: Duration = Duration::from_secs(30);
+const DOCKER_RETRY_MAX_DELAY: Duration = Duration::from_secs(10);
+// Watchers that fall further behind than this re-read the container instead
+const STATUS_UPDATES_CAPACITY: usize = 256;
+const RECONCILE_REQUESTS_CAPACITY: usize = 64;
+
 impl ProcessorService {
-    pub async fn new(processor_name: String, db: sea_orm::DatabaseConnection) -> Result<Self> {
-        let shutdown_signal = Arc::new(Mutex::new(false));
+    pub async fn new(
+        config: &Config,
+        db: sea_orm::DatabaseConnection,
+        docker: impl DockerApi + 'static,
+    ) -> Result<Self> {
+        let interval_secs = config.processor_interval_secs;
+        if interval_secs < 1 {
+            warn!(
+                "Invalid processor interval {}s, must be at least 1s",
+                interval_secs
+            );
+        }
+        let interval = Duration::from_secs(interval_secs.max(1));
+
+        let owner_lease = Duration::from_secs(config.owner_lease_secs.max(1));
+        if owner_lease <= interval {
+            warn!(
+                "Owner lease {}s is not longer than the processor interval {}s",
+                owner_lease.as_secs(),
+                interval.as_secs()
+            );
+        }
 
-        // Create Docker service internally since it's private to this module
-        let docker = DockerService::new().await?;
+        let (reconcile_requests, reconcile_receiver) = mpsc::channel(RECONCILE_REQUESTS_CAPACITY);
 
-        info!("Processor service initialized: {}", processor_name);
+        info!(
+            "Processor service initialized: {} (interval {}s, concurrency {})",
+            config.processor_name,
+            interval.as_secs(),
+            config.processor_concurrency.max(1)
+        );
 
         Ok(Self {
             db,
-            docker,
-            shutdown_signal,
+            name: config.processor_name.clone(),
+            owner_lease,
+            started_at: Utc::now(),
+            docker: Box::new(docker),
+            interval,
+            max_restart_retries: config.max_restart_retries,
+            default_stop_timeout_secs: config.stop_timeout_secs,
+            shutdown_drain_timeout: Duration::from_secs(config.shutdown_drain_timeout_secs),
+            stuck_timeout: Duration::from_secs(config.stuck_timeout_secs),
+            adopt_existing_containers: config.adopt_existing_containers,
+            log_archive: LogArchive::from_config(config),
+            docker_retry_attempts: config.docker_retry_attempts.max(1),
+            docker_retry_base_delay: Duration::from_millis(config.docker_retry_base_ms),
+            shutdown: CancellationToken::new(),
+            concurrency: config.processor_concurrency.max(1),
+            in_flight: Mutex::new(HashSet::new()),
+            readiness: watch::channel(Readiness::default()).0,
+            status_updates: broadcast::channel(STATUS_UPDATES_CAPACITY).0,
+            reconcile_requests,
+            reconcile_receiver: tokio::sync::Mutex::new(reconcile_receiver),
         })
     }
 
-    pub async fn start(&mut self) -> Result<()> {
+    pub fn reconcile_requests(&self) -> mpsc::Sender<ReconcileRequest> {
+        self.reconcile_requests.clone()
+    }
+
+    pub fn status_updates(&self) -> broadcast::Sender<ContainerStatusUpdate> {
+        self.status_updates.clone()
+    }
+
+    pub fn readiness(&self) -> watch::Receiver<Readiness> {
+        self.readiness.subscribe()
+    }
+
+    pub fn log_archive(&self) -> Option<LogArchive> {
+        self.log_archive.clone()
+    }
+
+    pub async fn start(&self) -> Result<()> {
         info!("Starting processor service...");
 
-        self.run_main_loop().await?;
+        if !self.wait_for_docker().await {
+            return Ok(());
+        }
+
+        // Announce this processor before claiming anything, others skip its containers from now on
+        if let Err(e) = self.write_heartbeat().await {
+            warn!("Failed to write processor heartbeat: {}", e);
+        }
+
+        if let Err(e) = self.reconcile_on_startup().await {
+            error!("Startup reconciliation failed: {}", e);
+        }
+        self.readiness.send_modify(|readiness| {
+            readiness.initialized = true;
+            readiness.last_tick = Some(Instant::now());
+        });
+
+        let result = self.run_main_loop().await;
+
+        // Make sure the rest of the service shuts down if the processor stops on its own
+        self.shutdown.cancel();
+        self.drain_removing().await;
+        if let Err(e) = self.release_ownership().await {
+            warn!("Failed to release container ownership: {}", e);
+        }
+        result
+    }
+
+    // One pass for maintenance runs. No heartbeat is written and ownership is kept, so a
+    // serving processor that shares the name isn't disturbed.
+    pub async fn reconcile_once(&self) -> Result<()> {
+        if !self.check_docker().await {
+            bail!("Docker is not reachable");
+        }
 
+        self.reconcile_on_startup().await?;
+        self.process_containers().await?;
         Ok(())
     }
 
-    async fn run_main_loop(&mut self) -> Result<()> {
+    // Retries until Docker answers, returns false if shutdown was requested first
+    async fn wait_for_docker(&self) -> bool {
+        let mut backoff = EVENTS_RECONNECT_MIN;
+
+        while !self.check_docker().await {
+            warn!(
+                "Docker is not reachable, retrying in {}s",
+                backoff.as_secs()
+            );
+            tokio::select! {
+                _ = self.shutdown.cancelled() => return false,
+                _ = tokio::time::sleep(backoff) => {}
+            }
+            backoff = (backoff * 2).min(DOCKER_WAIT_MAX);
+        }
+
+        true
+    }
+
+    async fn check_docker(&self) -> bool {
+        let result = self.docker.version().await;
+        let reachable = result.is_ok();
+        self.readiness.send_modify(|readiness| match result {
+            Ok(version) => {
+                readiness.docker_version = Some(version);
+                readiness.docker_error = None;
+            }
+            Err(e) => {
+                readiness.docker_version = None;
+                readiness.docker_error = Some(e.to_string());
+            }
+        });
+        reachable
+    }
+
+    // Docker may have moved on while the service was down, so statuses are corrected
+    // against the actual Docker state once before the loops start acting on them
+    async fn reconcile_on_startup(&self) -> Result<()> {
+        let containers = ContainerEntity::find()
+            .filter(ContainerColumn::DockerId.is_not_null())
+            .filter(ContainerColumn::DeletedAt.is_null())
+            .all(&self.db)
+            .await?;
+
+        let mut corrected = 0;
+        for container in &containers {
+            match self.ensure_owned(container).await {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(e) => {
+                    warn!("Failed to claim container {}: {}", container.id, e);
+                    continue;
+                }
+            }
+            match self
+                .reconcile_startup_container(container)
+                .instrument(container_span(container))
+                .await
+            {
+                Ok(true) => corrected += 1,
+                Ok(false) => {}
+                Err(e) => warn!(
+                    "Failed to reconcile container {} on startup: {}",
+                    container.id, e
+                ),
+            }
+        }
+
+        info!(
+            "Startup reconciliation checked {} containers, corrected {}",
+            containers.len(),
+            corrected
+        );
+        Ok(())
+    }
+
+    async fn reconcile_startup_container(&self, container: &ContainerModel) -> Result<bool> {
+        let Some(docker_id) = &container.docker_id else {
+            return Ok(false);
+        };
+        // Pending, Pulling and Removing are requests the main loop still has to carry out
+        if matches!(
+            container.status.as_str(),
+            "Pending" | "Pulling" | "Removing"
+        ) {
+            return Ok(false);
+        }
+
+        let Some(state) = self.docker.find_container_state(docker_id).await? else {
+            if container.status == "Failed" {
+                return Ok(false);
+            }
+            if container.auto_remove {
+                self.mark_auto_removed(container, None).await?;
+                return Ok(true);
+            }
+            warn!(
+                "Docker container {} for {} no longer exists",
+                docker_id, container.id
+            );
+            let updated = self
+                .update_container_status(
+                    container,
+                    "Failed",
+                    None,
+                    Some("Docker container no longer exists".to_string()),
+                )
+                .await?;
+            return Ok(updated.is_some());
+        };
+
+        let status = match (container.status.as_str(), state.status.as_str()) {
+            ("Running" | "Paused", "exited" | "dead") => {
+                info!(
+                    "Container {} exited while the service was down",
+                    container.id
+                );
+                self.handle_container_exit(container, docker_id, state.exit_code)
+                    .await?;
+                return Ok(true);
+            }
+            ("Running", "created") => "Created",
+            ("Running", "paused") => "Paused",
+            ("Created" | "Stopped" | "Failed" | "Paused", "running") => "Running",
+            _ => return Ok(false),
+        };
+
+        info!(
+            from_status = %container.status,
+            to_status = %status,
+            "Correcting container status to match Docker"
+        );
+        let details = StatusDetails {
+            started_at: state.started_at,
+            ..Default::default()
+        };
+        let updated = self
+            .write_container_status(container, status, None, None, details)
+            .await?;
+        Ok(updated.is_some())
+    }
+
+    async fn run_main_loop(&self) -> Result<()> {
         info!("Starting main processing loop");
 
-        let mut interval = tokio::time::interval(Duration::from_secs(10));
+        // Events give immediate reactions, the poll remains as a fallback reconciliation.
+        // Heartbeats run separately so a long reconciliation can't let them go stale.
+        tokio::join!(
+            self.run_poll_loop(),
+            self.run_event_loop(),
+            self.run_heartbeat_loop(),
+            self.run_reconcile_loop()
+        );
+
+        Ok(())
+    }
+
+    async fn run_poll_loop(&self) {
+        let mut interval = tokio::time::interval(self.interval);
 
         loop {
-            if *self.shutdown_signal.lock().unwrap() {
-                info!("Shutdown signal received, stopping processor");
-                break;
+            // Shutdown is only observed between ticks so a reconciliation always runs to completion
+            tokio::select! {
+                _ = self.shutdown.cancelled() => {
+                    info!("Shutdown signal received, stopping processor");
+                    break;
+                }
+                _ = interval.tick() => {}
             }
 
-            interval.tick().await;
-
-            if let Err(e) = self.process_containers().await {
+            if !self.check_docker().await {
+                warn!("Docker is not reachable, skipping processing tick");
+            } else if let Err(e) = self.process_containers().await {
                 error!("Error in main processing loop: {}", e);
             }
+
+            if let Err(e) = self.refresh_status_metrics().await {
+                warn!("Failed to refresh container metrics: {}", e);
+            }
+
+            self.readiness
+                .send_modify(|readiness| readiness.last_tick = Some(Instant::now()));
+        }
+    }
+
+    async fn run_heartbeat_loop(&self) {
+        let mut interval = tokio::time::interval(self.owner_lease / 3);
+
+        loop {
+            tokio::select! {
+                _ = self.shutdown.cancelled() => break,
+                _ = interval.tick() => {}
+            }
+
+            if let Err(e) = self.write_heartbeat().await {
+                warn!("Failed to write processor heartbeat: {}", e);
+            }
         }
+    }
+
+    async fn run_reconcile_loop(&self) {
+        let mut receiver = self.reconcile_receiver.lock().await;
+
+        // Requests that already started still finish after shutdown, like a poll tick
+        futures::stream::poll_fn(|cx| receiver.poll_recv(cx))
+            .take_until(self.shutdown.cancelled())
+            .for_each_concurrent(self.concurrency, |request| async move {
+                info!("Reconcile requested for container {}", request.container_id);
+                let result = self.reconcile_container(&request.container_id).await;
+                if let Err(e) = &result {
+                    error!(
+                        "Error reconciling container {}: {}",
+                        request.container_id, e
+                    );
+                }
+                // The caller may have gone away in the meantime
+                let _ = request.done.send(result);
+            })
+            .await;
+
+        // Dropping queued requests answers their callers instead of leaving them waiting
+        receiver.close();
+        while receiver.try_recv().is_ok() {}
+    }
 
+    async fn write_heartbeat(&self) -> Result<()> {
+        HeartbeatEntity::insert(HeartbeatActiveModel {
+            name: Set(self.name.clone()),
+            started_at: Set(self.started_at),
+            last_seen: Set(Utc::now()),
+        })
+        .on_conflict(
+            OnConflict::column(HeartbeatColumn::Name)
+                .update_columns([HeartbeatColumn::StartedAt, HeartbeatColumn::LastSeen])
+                .to_owned(),
+        )
+        .exec(&self.db)
+        .await?;
         Ok(())
     }
 
+    async fn run_event_loop(&self) {
+        let mut backoff = EVENTS_RECONNECT_MIN;
+
+        while !self.shutdown.is_cancelled() {
+            info!("Subscribing to Docker container events");
+            let events = self.docker.container_events();
+            futures::pin_mut!(events);
+
+            loop {
+                let event = tokio::select! {
+                    _ = self.shutdown.cancelled() => return,
+                    event = events.next() => event,
+                };
+
+                match event {
+                    Some(Ok(docker_id)) => {
+                        backoff = EVENTS_RECONNECT_MIN;
+                        if let Err(e) = self.process_container_event(&docker_id).await {
+                            error!("Error handling Docker event for {}: {}", docker_id, e);
+                        }
+                    }
+                    Some(Err(e)) => {
+                        warn!("Docker events stream failed: {}", e);
+                        break;
+                    }
+                    None => {
+                        warn!("Docker events stream closed");
+                        break;
+                    }
+                }
+            }
+
+            warn!("Reconnecting to Docker events in {}s", backoff.as_secs());
+            tokio::select! {
+                _ = self.shutdown.cancelled() => return,
+                _ = tokio::time::sleep(backoff) => {}
+            }
+            backoff = (backoff * 2).min(EVENTS_RECONNECT_MAX);
+        }
+    }
+
+    async fn process_container_event(&self, docker_id: &str) -> Result<()> {
+        // Events for containers nebulet doesn't manage are ignored
+        let Some(container) = ContainerEntity::find()
+            .filter(ContainerColumn::DockerId.eq(docker_id))
+            .filter(ContainerColumn::DeletedAt.is_null())
+            .one(&self.db)
+            .await?
+        else {
+            return Ok(());
+        };
+
+        info!(
+            "Docker event for container {}, reconciling immediately",
+            container.id
+        );
+        self.reconcile_container(&container.id).await
+    }
+
     async fn process_containers(&self) -> Result<()> {
-        let containers = ContainerEntity::find().all(&self.db).await?;
+        let containers = ContainerEntity::find()
+            .filter(ContainerColumn::DeletedAt.is_null())
+            .filter(self.claimable(Utc::now()))
+            .all(&self.db)
+            .await?;
+
+        futures::stream::iter(containers)
+            .for_each_concurrent(self.concurrency, |container| async move {
+                if let Err(e) = self.reconcile_container(&container.id).await {
+                    error!("Error processing container {}: {}", container.id, e);
+                }
+            })
+            .await;
+
+        Ok(())
+    }
 
-        for container in containers {
-            if let Err(e) = self.process_single_container(&container).await {
-                error!("Error processing container {}: {}", container.id, e);
+    fn claim(&self, container_id: &str) -> Option<InFlightGuard<'_>> {
+        let mut in_flight = self.in_flight.lock().ok()?;
+        if !in_flight.insert(container_id.to_string()) {
+            return None;
+        }
+
+        Some(InFlightGuard {
+            in_flight: &self.in_flight,
+            container_id: container_id.to_string(),
+        })
+    }
+
+    async fn reconcile_container(&self, container_id: &str) -> Result<()> {
+        let Some(_guard) = self.claim(container_id) else {
+            debug!(
+                "Container {} is already being reconciled, skipping",
+                container_id
+            );
+            return Ok(());
+        };
+
+        // Re-read under the claim, the row may have changed while earlier work was running
+        let Some(container) = ContainerEntity::find_by_id(container_id.to_string())
+            .filter(ContainerColumn::DeletedAt.is_null())
+            .one(&self.db)
+            .await?
+        else {
+            return Ok(());
+        };
+
+        if !self.ensure_owned(&container).await? {
+            debug!(
+                "Container {} is owned by another processor, skipping",
+                container_id
+            );
+            return Ok(());
+        }
+
+        self.process_single_container(&container)
+            .instrument(container_span(&container))
+            .await
+    }
+
+    // Final pass over Removing containers once the loops have stopped, so a restart doesn't
+    // leave their Docker containers behind. Bounded by the drain timeout, anything still
+    // Removing afterwards is logged for an operator to clean up.
+    async fn drain_removing(&self) {
+        if self.shutdown_drain_timeout.is_zero() {
+            return;
+        }
+
+        let drain = async {
+            let containers = self.removing_containers().await?;
+            if !containers.is_empty() {
+                info!(
+                    "Finishing removal of {} containers before exiting",
+                    containers.len()
+                );
             }
+            futures::stream::iter(containers)
+                .for_each_concurrent(self.concurrency, |container| async move {
+                    if let Err(e) = self.reconcile_container(&container.id).await {
+                        error!("Error removing container {}: {}", container.id, e);
+                    }
+                })
+                .await;
+            anyhow::Ok(())
+        };
+        match tokio::time::timeout(self.shutdown_drain_timeout, drain).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => warn!("Failed to finish removals before exiting: {}", e),
+            Err(_) => warn!(
+                "Removals did not finish within {}s of shutdown",
+                self.shutdown_drain_timeout.as_secs()
+            ),
         }
 
+        match self.removing_containers().await {
+            Ok(containers) => {
+                for container in containers {
+                    let _span = container_span(&container).entered();
+                    warn!(
+                        docker_id = container.docker_id.as_deref().unwrap_or("none"),
+                        "Container is still Removing at exit, its Docker container may need manual cleanup"
+                    );
+                }
+            }
+            Err(e) => warn!("Failed to list containers left in Removing: {}", e),
+        }
+    }
+
+    async fn removing_containers(&self) -> Result<Vec<ContainerModel>> {
+        Ok(ContainerEntity::find()
+            .filter(ContainerColumn::DeletedAt.is_null())
+            .filter(ContainerColumn::Status.eq("Removing"))
+            .filter(self.claimable(Utc::now()))
+            .all(&self.db)
+            .await?)
+    }
+
+    // Rows without an owner, owned by this processor or whose owner's heartbeat went stale
+    fn claimable(&self, now: DateTime<Utc>) -> Condition {
+        Condition::any()
+            .add(ContainerColumn::Owner.is_null())
+            .add(ContainerColumn::Owner.eq(self.name.clone()))
+            .add(
+                ContainerColumn::Owner.not_in_subquery(
+                    Query::select()
+                        .column(HeartbeatColumn::Name)
+                        .from(HeartbeatEntity)
+                        .and_where(HeartbeatColumn::LastSeen.gte(now - self.owner_lease))
+                        .to_owned(),
+                ),
+            )
+    }
+
+    // Claims the container unless another live processor owns it. The conditional update
+    // lets exactly one processor win when several try at once. Ownership columns aren't
+    // part of the version check, so claiming doesn't conflict with API writes.
+    async fn ensure_owned(&self, container: &ContainerModel) -> Result<bool> {
+        if container.owner.as_deref() == Some(self.name.as_str()) {
+            return Ok(true);
+        }
+
+        let now = Utc::now();
+        let result = ContainerEntity::update_many()
+            .col_expr(ContainerColumn::Owner, Expr::value(self.name.clone()))
+            .filter(ContainerColumn::Id.eq(container.id.clone()))
+            .filter(self.claimable(now))
+            .exec(&self.db)
+            .await?;
+        if result.rows_affected == 0 {
+            return Ok(false);
+        }
+
+        if let Some(previous) = &container.owner {
+            info!(
+                "Took over container {} from processor {}",
+                container.id, previous
+            );
+        }
+        Ok(true)
+    }
+
+    // Lets other processors pick the containers up right away instead of after the lease,
+    // and drops the heartbeat so the processor isn't listed as stale
+    async fn release_ownership(&self) -> Result<()> {
+        let result = ContainerEntity::update_many()
+            .col_expr(ContainerColumn::Owner, Expr::value(Option::<String>::None))
+            .filter(ContainerColumn::Owner.eq(self.name.clone()))
+            .exec(&self.db)
+            .await?;
+        HeartbeatEntity::delete_by_id(self.name.clone())
+            .exec(&self.db)
+            .await?;
+        info!("Released ownership of {} containers", result.rows_affected);
         Ok(())
     }
 
+    async fn refresh_status_metrics(&self) -> Result<()> {
+        let counts: Vec<(String, i64)> = ContainerEntity::find()
+            .select_only()
+            .column(ContainerColumn::Status)
+            .column_as(Expr::col(ContainerColumn::Id).count(), "count")
+            .filter(ContainerColumn::DeletedAt.is_null())
+            .group_by(ContainerColumn::Status)
+            .into_tuple()
+            .all(&self.db)
+            .await?;
+
+        let counts: Vec<(ContainerStatus, u64)> = counts
+            .into_iter()
+            .filter_map(|(status, count)| Some((status.parse().ok()?, count as u64)))
+            .collect();
+        crate::metrics::set_containers_by_status(&counts);
+
+        Ok(())
+    }
+
+    // Docker is acted on first and the outcome recorded afterwards, the two can't share a
+    // transaction. When recording fails the row keeps its old status and the next pass
+    // redoes the action: stop and remove are idempotent in Docker, so it converges and
+    // records the result then. Creates and starts are undone right away instead. A Docker
+    // container the row doesn't know about would block the name on the next attempt, and
+    // one running while its row says otherwise would run unaccounted until the next pass.
     async fn process_single_container(&self, container: &ContainerModel) -> Result<()> {
+        if let Some(stuck_for) = self.stuck_for(container) {
+            warn!(
+                status = %container.status,
+                "Container made no progress for {}s, failing it",
+                stuck_for.as_secs()
+            );
+            self.update_container_status(
+                container,
+                "Failed",
+                None,
+                Some(format!(
+                    "Stuck timeout: still {} after {}s",
+                    container.status,
+                    stuck_for.as_secs()
+                )),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        // Env or image changed through the API, the old container can't pick that up
+        if matches!(container.status.as_str(), "Created" | "Running")
+            && container
+                .spec_hash
+                .as_ref()
+                .is_some_and(|applied| *applied != container.desired_spec_hash())
+        {
+            info!("Container spec changed, scheduling recreate");
+            self.update_container_status(container, "Pending", None, None)
+                .await?;
+            return Ok(());
+        }
+
         match container.status.as_str() {
-            "Pending" => {
+            "Pending" | "Pulling" => {
+                let mut container = container.clone();
+
+                // A docker_id on a Pending container means it was updated and needs a recreate
+                if let Some(docker_id) = &container.docker_id {
+                    info!(
+                        "Removing previous Docker container before recreate: {}",
+                        docker_id
+                    );
+                    let stop_timeout = self.stop_timeout(&container);
+                    let (result, _) = self
+                        .retry_docker("stop", || {
+                            self.docker.stop_container(docker_id, stop_timeout)
+                        })
+                        .await;
+                    if let Some(e) = result.err().filter(|e| !is_container_gone(e)) {
+                        warn!("Failed to stop container {}: {}", docker_id, e);
+                    }
+
+                    let result = self.docker.remove_container(docker_id).await;
+                    if let Some(e) = result.err().filter(|e| !is_container_gone(e)) {
+                        warn!("Failed to remove container {}: {}", docker_id, e);
+                    }
+                }
+
+                // Surface the pull while it happens, create_container pulls missing images
+                match self.docker.image_exists(&container.image).await {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        info!("Image not present locally, pulling: {}", container.image);
+                        match self
+                            .update_container_status(&container, "Pulling", None, None)
+                            .await?
+                        {
+                            Some(updated) => container = updated,
+                            None => return Ok(()),
+                        }
+                    }
+                    Err(e) => warn!("Failed to check image {}: {}", container.image, e),
+                }
+
                 // Container is pending creation - create it in Docker
                 info!("Creating container in Docker: {}", container.name);
-                match self
-                    .docker
-                    .create_container(&crate::models::v1::container::CreateContainerRequest {
-                        name: container.name.clone(),
-                        image: container.image.clone(),
-                    })
-                    .await
-                {
+                let request = CreateContainerRequest::from(&container);
+                let (result, attempts) = self
+                    .retry_docker("create", || self.docker.create_container(&request))
+                    .await;
+                match result {
                     Ok(docker_id) => {
-                        self.update_container_status(&container.id, "Created", Some(docker_id))
-                            .await?;
+                        // Recorded once, recreating for the same image keeps the first digest
+                        let image_digest = match container.image_digest {
+                            Some(_) => None,
+                            None => self.resolve_image_digest(&container.image).await,
+                        };
+                        let details = StatusDetails {
+                            attempts: Some(attempts),
+                            image_digest,
+                            docker_created_at: self.docker_created_at(&docker_id).await,
+                            spec_hash: Some(container.desired_spec_hash()),
+                            ..Default::default()
+                        };
+                        let written = self
+                            .write_container_status(
+                                &container,
+                                "Created",
+                                Some(docker_id.clone()),
+                                None,
+                                details,
+                            )
+                            .await;
+                        if let Err(e) = written {
+                            error!(
+                                "Failed to record Docker container {}, removing it again: {}",
+                                docker_id, e
+                            );
+                            if let Err(e) = self.docker.remove_container(&docker_id).await {
+                                warn!("Failed to remove container {}: {}", docker_id, e);
+                            }
+                            return Err(e);
+                        }
                         info!("Container created successfully: {}", container.id);
                     }
                     Err(e) => {
+                        let name_conflict = matches!(
+                            e.downcast_ref::<DockerError>(),
+                            Some(DockerError::NameConflict(_))
+                        );
+                        if name_conflict
+                            && self.adopt_existing_containers
+                            && self.adopt_existing_container(&container).await?
+                        {
+                            return Ok(());
+                        }
+
                         error!("Failed to create container {}: {}", container.id, e);
-                        self.update_container_status(&container.id, "Failed", None)
-                            .await?;
+                        self.update_container_status_after_attempts(
+                            &container,
+                            "Failed",
+                            None,
+                            Some(format!("{:#}", e)),
+                            attempts,
+                        )
+                        .await?;
                     }
                 }
             }
@@ -102,24 +890,91 @@ impl ProcessorService {
                 // Container is created but not started
                 if let Some(docker_id) = &container.docker_id {
                     info!("Starting container: {}", docker_id);
-                    if let Err(e) = self.docker.start_container(docker_id).await {
+                    let (result, attempts) = self
+                        .retry_docker("start", || self.docker.start_container(docker_id))
+                        .await;
+                    if let Err(e) = result {
                         error!("Failed to start container {}: {}", docker_id, e);
-                        self.update_container_status(&container.id, "Failed", None)
-                            .await?;
+                        self.update_container_status_after_attempts(
+                            container,
+                            "Failed",
+                            None,
+                            Some(format!("{:#}", e)),
+                            attempts,
+                        )
+                        .await?;
                     } else {
-                        self.update_container_status(&container.id, "Running", None)
-                            .await?;
+                        self.mark_started(container, docker_id, attempts).await?;
                         info!("Container started successfully: {}", container.id);
                     }
                 }
             }
-            "Running" => {
-                // Check if container is still running
+            "Running" | "Paused" => {
+                // Check if container is still running, and follow pauses made outside nebulet
                 if let Some(docker_id) = &container.docker_id {
-                    let status = self.docker.get_container_status(docker_id).await?;
-                    if status != "running" {
-                        self.update_container_status(&container.id, &status, None)
+                    let state = match self.docker.get_container_state(docker_id).await {
+                        Ok(state) => state,
+                        // Removed behind nebulet's back, polling it again would fail forever
+                        Err(e) if is_container_gone(&e) && container.auto_remove => {
+                            return self.mark_auto_removed(container, None).await;
+                        }
+                        Err(e) if is_container_gone(&e) => {
+                            warn!(
+                                "Docker container {} of {} no longer exists",
+                                docker_id, container.id
+                            );
+                            self.update_container_status(
+                                container,
+                                "Failed",
+                                None,
+                                Some("Container no longer exists in Docker".to_string()),
+                            )
                             .await?;
+                            return Ok(());
+                        }
+                        Err(e) => return Err(e),
+                    };
+                    match state.status.as_str() {
+                        "exited" | "dead" => {
+                            self.handle_container_exit(container, docker_id, state.exit_code)
+                                .await?;
+                        }
+                        UNKNOWN_CONTAINER_STATUS => {
+                            warn!(
+                                "Could not determine Docker status for container {}",
+                                docker_id
+                            );
+                        }
+                        status => match ContainerStatus::from_docker_state(status) {
+                            Some(mapped) if mapped.as_str() != container.status => {
+                                info!(
+                                    "Container {} is {} in Docker, recording it as {}",
+                                    container.id,
+                                    status,
+                                    mapped.as_str()
+                                );
+                                self.update_container_status(
+                                    container,
+                                    mapped.as_str(),
+                                    None,
+                                    None,
+                                )
+                                .await?;
+                            }
+                            Some(_) => {
+                                // Resumes capturing after a restart of the service
+                                if container.status == "Running" {
+                                    self.capture_logs(container, docker_id);
+                                }
+                                self.record_health(container, state.health.clone()).await?
+                            }
+                            None => {
+                                debug!(
+                                    "Container {} is {} in Docker, leaving it as {}",
+                                    container.id, status, container.status
+                                );
+                            }
+                        },
                     }
                 }
             }
@@ -127,26 +982,101 @@ impl ProcessorService {
                 // Container is marked for removal
                 if let Some(docker_id) = &container.docker_id {
                     info!("Removing container: {}", docker_id);
-                    if let Err(e) = self.docker.stop_container(docker_id).await {
+                    let stop_timeout = self.stop_timeout(container);
+                    let (result, _) = self
+                        .retry_docker("stop", || {
+                            self.docker.stop_container(docker_id, stop_timeout)
+                        })
+                        .await;
+                    if let Some(e) = result.err().filter(|e| !is_container_gone(e)) {
                         warn!("Failed to stop container {}: {}", docker_id, e);
                     }
 
-                    if let Err(e) = self.docker.remove_container(docker_id).await {
+                    let result = self.docker.remove_container(docker_id).await;
+                    if let Some(e) = result.err().filter(|e| !is_container_gone(e)) {
                         warn!("Failed to remove container {}: {}", docker_id, e);
                     }
+                    info!(
+                        "Cleaned up Docker container {} for {}",
+                        docker_id, container.id
+                    );
+                } else {
+                    // Deleted before the processor got to create it, nothing to clean up
+                    info!(
+                        "Container {} was never created in Docker, skipping cleanup",
+                        container.id
+                    );
                 }
 
-                // Delete from database
-                ContainerEntity::delete_by_id(container.id.clone())
-                    .exec(&self.db)
+                self.mark_removed(container, container.exit_code, None)
                     .await?;
-                info!("Container removed from database: {}", container.id);
             }
-            "Stopped" | "Failed" => {
-                // Clean up stopped/failed containers
+            "Restarting" => {
+                // Container restart was requested - stop then start it again
                 if let Some(docker_id) = &container.docker_id {
-                    if let Err(e) = self.docker.remove_container(docker_id).await {
-                        warn!("Failed to remove container {}: {}", docker_id, e);
+                    info!("Restarting container: {}", docker_id);
+                    let stop_timeout = self.stop_timeout(container);
+                    let (result, _) = self
+                        .retry_docker("stop", || {
+                            self.docker.stop_container(docker_id, stop_timeout)
+                        })
+                        .await;
+                    if let Some(e) = result.err().filter(|e| !is_container_gone(e)) {
+                        warn!("Failed to stop container {}: {}", docker_id, e);
+                    }
+
+                    let (result, attempts) = self
+                        .retry_docker("start", || self.docker.start_container(docker_id))
+                        .await;
+                    if let Err(e) = result {
+                        error!("Failed to restart container {}: {}", docker_id, e);
+                        self.update_container_status_after_attempts(
+                            container,
+                            "Failed",
+                            None,
+                            Some(format!("{:#}", e)),
+                            attempts,
+                        )
+                        .await?;
+                    } else {
+                        self.mark_started(container, docker_id, attempts).await?;
+                        info!("Container restarted successfully: {}", container.id);
+                    }
+                }
+            }
+            "Stopped" => {
+                // Keep stopped containers around so they can be started again
+                if let Some(docker_id) = &container.docker_id {
+                    let status = match self.docker.get_container_status(docker_id).await {
+                        Err(e) if is_container_gone(&e) && container.auto_remove => {
+                            return self.mark_auto_removed(container, None).await;
+                        }
+                        result => result?,
+                    };
+                    if status == "running" {
+                        info!("Stopping container: {}", docker_id);
+                        let stop_timeout = self.stop_timeout(container);
+                        self.retry_docker("stop", || {
+                            self.docker.stop_container(docker_id, stop_timeout)
+                        })
+                        .await
+                        .0?;
+                    }
+                    // Except auto_remove ones, Docker removes those once they stop
+                    if container.auto_remove {
+                        return self.mark_auto_removed(container, None).await;
+                    }
+                }
+            }
+            "Failed" => {
+                // Clean up failed containers, then forget the Docker id so later ticks leave
+                // them alone. Starting one again creates a new Docker container anyway
+                if let Some(docker_id) = &container.docker_id {
+                    match self.docker.remove_container(docker_id).await {
+                        Err(e) if !is_container_gone(&e) => {
+                            warn!("Failed to remove container {}: {}", docker_id, e);
+                        }
+                        _ => self.clear_docker_id(container).await?,
                     }
                 }
             }
@@ -159,34 +1089,1270 @@ impl ProcessorService {
         Ok(())
     }
 
+    // A container left behind by an earlier run blocks the name, take it over when it
+    // runs the same image. Returns false when it can't be adopted.
+    async fn adopt_existing_container(&self, container: &ContainerModel) -> Result<bool> {
+        let Some(existing) = self.docker.find_container(&container.name).await? else {
+            return Ok(false);
+        };
+        if existing.image.as_deref() != Some(container.image.as_str()) {
+            warn!(
+                "Not adopting Docker container {} for {}, it runs image {:?} instead of {}",
+                existing.id, container.id, existing.image, container.image
+            );
+            return Ok(false);
+        }
+
+        // Anything that isn't running is left as Created so the processor starts it
+        let status = match existing.state.status.as_str() {
+            "running" => "Running",
+            _ => "Created",
+        };
+        info!(
+            docker_id = %existing.id,
+            to_status = %status,
+            "Adopting existing Docker container"
+        );
+        let details = StatusDetails {
+            started_at: existing.state.started_at,
+            docker_created_at: existing.created_at,
+            spec_hash: Some(container.desired_spec_hash()),
+            ..Default::default()
+        };
+        self.write_container_status(container, status, Some(existing.id), None, details)
+            .await?;
+        Ok(true)
+    }
+
+    // Retries transient Docker failures with exponential backoff and returns the final
+    // result together with the number of attempts it took
+    async fn retry_docker<T, F, Fut>(&self, operation: &str, mut call: F) -> (Result<T>, u32)
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut delay = self.docker_retry_base_delay;
+        let mut attempt = 1;
+
+        loop {
+            match call().await {
+                Err(e) if attempt < self.docker_retry_attempts && is_transient(&e) => {
+                    warn!(
+                        "Transient Docker error during {} (attempt {}/{}), retrying in {}ms: {}",
+                        operation,
+                        attempt,
+                        self.docker_retry_attempts,
+                        delay.as_millis(),
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(DOCKER_RETRY_MAX_DELAY);
+                    attempt += 1;
+                }
+                result => return (result, attempt),
+            }
+        }
+    }
+
+    fn stop_timeout(&self, container: &ContainerModel) -> u64 {
+        container
+            .stop_timeout_secs
+            .map(|timeout| timeout as u64)
+            .unwrap_or(self.default_stop_timeout_secs)
+    }
+
+    // Apply the container's restart policy after Docker reports it exited on its own
+    async fn handle_container_exit(
+        &self,
+        container: &ContainerModel,
+        docker_id: &str,
+        exit_code: Option<i64>,
+    ) -> Result<()> {
+        if container.auto_remove {
+            return self.mark_auto_removed(container, exit_code).await;
+        }
+
+        let failed = exit_code != Some(0);
+        let should_restart = match container.restart_policy.as_deref() {
+            Some("always") => true,
+            Some("on-failure") => {
+                failed && container.restart_count < i64::from(self.max_restart_retries)
+            }
+            _ => false,
+        };
+
+        if should_restart {
+            info!(
+                exit_code,
+                restart = container.restart_count + 1,
+                "Restarting exited container"
+            );
+            // Passing through Restarting records the exit and the restart as transitions,
+            // and Running afresh gives the new run its own start time
+            let details = StatusDetails {
+                exit_code: Some(exit_code),
+                restart_count: Some(container.restart_count + 1),
+                ..Default::default()
+            };
+            let Some(container) = self
+                .write_container_status(container, "Restarting", None, None, details)
+                .await?
+            else {
+                return Ok(());
+            };
+
+            let (result, attempts) = self
+                .retry_docker("start", || self.docker.start_container(docker_id))
+                .await;
+            match result {
+                Ok(()) => {
+                    self.mark_started(&container, docker_id, attempts).await?;
+                }
+                Err(e) => {
+                    error!("Failed to restart container {}: {}", docker_id, e);
+                    self.update_container_status_after_attempts(
+                        &container,
+                        "Failed",
+                        None,
+                        Some(format!("{:#}", e)),
+                        attempts,
+                    )
+                    .await?;
+                }
+            }
+            return Ok(());
+        }
+
+        let (status, error_message) = match exit_code {
+            Some(0) => ("Stopped", None),
+            Some(code) => (
+                "Failed",
+                Some(format!("Container exited with code {}", code)),
+            ),
+            None => (
+                "Failed",
+                Some("Container exited with an unknown exit code".to_string()),
+            ),
+        };
+        let details = StatusDetails {
+            exit_code: Some(exit_code),
+            ..Default::default()
+        };
+        self.write_container_status(container, status, None, error_message, details)
+            .await?;
+        Ok(())
+    }
+
+    // Time spent in a status that should only be passed through, once it exceeds the stuck
+    // timeout. Repeated transient errors would otherwise keep a container there forever.
+    fn stuck_for(&self, container: &ContainerModel) -> Option<Duration> {
+        if self.stuck_timeout.is_zero()
+            || !matches!(
+                container.status.as_str(),
+                "Pending" | "Pulling" | "Created" | "Restarting"
+            )
+        {
+            return None;
+        }
+        let stuck_for = (Utc::now() - container.status_changed_at?).to_std().ok()?;
+        (stuck_for > self.stuck_timeout).then_some(stuck_for)
+    }
+
+    // Docker removes auto_remove containers on exit by itself, so only the row is left to
+    // update. The exit code is None when Docker was quicker than the processor.
+    async fn mark_auto_removed(
+        &self,
+        container: &ContainerModel,
+        exit_code: Option<i64>,
+    ) -> Result<()> {
+        info!(
+            exit_code,
+            "Container exited, removing it as requested by auto_remove"
+        );
+        self.mark_removed(
+            container,
+            exit_code.or(container.exit_code),
+            Some("Removed after exit by auto_remove".to_string()),
+        )
+        .await
+    }
+
+    // Keep the row as Removed so the container's history stays queryable
+    async fn mark_removed(
+        &self,
+        container: &ContainerModel,
+        exit_code: Option<i64>,
+        reason: Option<String>,
+    ) -> Result<()> {
+        let now = Utc::now();
+        let finished_at = match container.status.as_str() {
+            "Running" | "Paused" => Some(now),
+            _ => container.finished_at,
+        };
+        let txn = self.db.begin().await?;
+        let result = ContainerEntity::update_many()
+            .col_expr(ContainerColumn::Status, Expr::value("Removed"))
+            .col_expr(ContainerColumn::StatusChangedAt, Expr::value(now))
+            .col_expr(ContainerColumn::ExitCode, Expr::value(exit_code))
+            .col_expr(ContainerColumn::FinishedAt, Expr::value(finished_at))
+            .col_expr(ContainerColumn::DeletedAt, Expr::value(now))
+            .col_expr(ContainerColumn::UpdatedAt, Expr::value(now))
+            .col_expr(ContainerColumn::Owner, Expr::value(Option::<String>::None))
+            .col_expr(ContainerColumn::Version, Expr::value(container.version + 1))
+            .filter(ContainerColumn::Id.eq(container.id.clone()))
+            .filter(ContainerColumn::Version.eq(container.version))
+            .exec(&txn)
+            .await?;
+        if result.rows_affected == 0 {
+            txn.rollback().await?;
+            warn!(
+                "Container {} changed concurrently, not marking it removed",
+                container.id
+            );
+            return Ok(());
+        }
+        audit_log::record_transition(
+            &txn,
+            &container.id,
+            Some(&container.status),
+            "Removed",
+            reason,
+        )
+        .await?;
+        txn.commit().await?;
+        info!(
+            from_status = %container.status,
+            to_status = %"Removed",
+            "Container marked as removed"
+        );
+        self.publish_status(&ContainerModel {
+            status: "Removed".to_string(),
+            exit_code,
+            finished_at,
+            status_changed_at: Some(now),
+            updated_at: now,
+            deleted_at: Some(now),
+            ..container.clone()
+        });
+        Ok(())
+    }
+
+    // Only writes if the row is still at the version the decision was made from, returns the
+    // updated row or None when a concurrent write (e.g. an API delete) got there first
     async fn update_container_status(
         &self,
-        container_id: &str,
+        container: &ContainerModel,
         status: &str,
         docker_id: Option<String>,
-    ) -> Result<()> {
-        let container = ContainerEntity::find_by_id(container_id.to_string())
-            .one(&self.db)
-            .await?
-            .ok_or_else(|| anyhow::anyhow!("Container not found"))?;
+        error_message: Option<String>,
+    ) -> Result<Option<ContainerModel>> {
+        self.write_container_status(
+            container,
+            status,
+            docker_id,
+            error_message,
+            StatusDetails::default(),
+        )
+        .await
+    }
 
-        let mut active_model: ContainerActiveModel = container.into();
-        active_model.status = Set(status.to_string());
-        active_model.updated_at = Set(Utc::now().to_rfc3339());
+    // A missing digest only costs reproducibility, so it never fails the create
+    async fn resolve_image_digest(&self, image: &str) -> Option<String> {
+        match self.docker.image_digest(image).await {
+            Ok(Some(digest)) => {
+                info!("Image {} resolved to {}", image, digest);
+                Some(digest)
+            }
+            Ok(None) => {
+                debug!("Image {} has no registry digest", image);
+                None
+            }
+            Err(e) => {
+                warn!("Failed to resolve digest of image {}: {}", image, e);
+                None
+            }
+        }
+    }
 
-        if let Some(docker_id) = docker_id {
-            active_model.docker_id = Set(Some(docker_id));
+    // Only informational, so an inspect failure leaves it unset instead of failing the create
+    async fn docker_created_at(&self, docker_id: &str) -> Option<DateTime<Utc>> {
+        match self.docker.find_container(docker_id).await {
+            Ok(existing) => existing.and_then(|existing| existing.created_at),
+            Err(e) => {
+                debug!("Could not read creation time of {}: {:#}", docker_id, e);
+                None
+            }
         }
+    }
 
-        active_model.update(&self.db).await?;
+    // Same as update_container_status, also recording how many attempts the Docker call took
+    async fn update_container_status_after_attempts(
+        &self,
+        container: &ContainerModel,
+        status: &str,
+        docker_id: Option<String>,
+        error_message: Option<String>,
+        attempts: u32,
+    ) -> Result<Option<ContainerModel>> {
+        let details = StatusDetails {
+            attempts: Some(attempts),
+            ..Default::default()
+        };
+        self.write_container_status(container, status, docker_id, error_message, details)
+            .await
+    }
+
+    // Health changes only touch their own column, so they neither bump the version nor
+    // show up in the audit log. A concurrent change skips the write until the next poll
+    async fn record_health(
+        &self,
+        container: &ContainerModel,
+        health: Option<String>,
+    ) -> Result<()> {
+        if container.health == health {
+            return Ok(());
+        }
+        match health.as_deref() {
+            Some("unhealthy") => warn!("Container {} is unhealthy", container.id),
+            Some(health) => info!("Container {} health is now {}", container.id, health),
+            None => info!("Container {} no longer reports health", container.id),
+        }
+        ContainerEntity::update_many()
+            .col_expr(ContainerColumn::Health, Expr::value(health))
+            .filter(ContainerColumn::Id.eq(container.id.clone()))
+            .filter(ContainerColumn::Version.eq(container.version))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
 
-        info!("Updated container {} status to {}", container_id, status);
+    // Not a status change, so it stays out of the audit log. A concurrent change keeps the id
+    // and the next tick tries again
+    async fn clear_docker_id(&self, container: &ContainerModel) -> Result<()> {
+        let result = ContainerEntity::update_many()
+            .col_expr(
+                ContainerColumn::DockerId,
+                Expr::value(Option::<String>::None),
+            )
+            .col_expr(ContainerColumn::UpdatedAt, Expr::value(Utc::now()))
+            .col_expr(ContainerColumn::Version, Expr::value(container.version + 1))
+            .filter(ContainerColumn::Id.eq(container.id.clone()))
+            .filter(ContainerColumn::Version.eq(container.version))
+            .exec(&self.db)
+            .await?;
+        if result.rows_affected > 0 {
+            info!(
+                "Cleaned up Docker container of failed container {}",
+                container.id
+            );
+        }
         Ok(())
     }
 
+    // Records a successful start together with the StartedAt Docker reports for it
+    async fn mark_started(
+        &self,
+        container: &ContainerModel,
+        docker_id: &str,
+        attempts: u32,
+    ) -> Result<Option<ContainerModel>> {
+        let started_at = match self.docker.get_container_state(docker_id).await {
+            Ok(state) => state.started_at,
+            Err(e) => {
+                debug!("Could not read start time of {}: {:#}", docker_id, e);
+                None
+            }
+        };
+        let details = StatusDetails {
+            attempts: Some(attempts),
+            started_at,
+            ..Default::default()
+        };
+        let updated = match self
+            .write_container_status(container, "Running", None, None, details)
+            .await
+        {
+            Ok(updated) => updated,
+            Err(e) => {
+                error!(
+                    "Failed to record start of {}, stopping it again: {}",
+                    docker_id, e
+                );
+                let stop_timeout = self.stop_timeout(container);
+                if let Err(e) = self.docker.stop_container(docker_id, stop_timeout).await {
+                    warn!("Failed to stop container {}: {}", docker_id, e);
+                }
+                return Err(e);
+            }
+        };
+        if updated.is_some() {
+            self.capture_logs(container, docker_id);
+        }
+        Ok(updated)
+    }
+
+    fn capture_logs(&self, container: &ContainerModel, docker_id: &str) {
+        if let Some(log_archive) = &self.log_archive {
+            log_archive.capture(self.docker.as_ref(), &container.id, docker_id);
+        }
+    }
+
+    async fn write_container_status(
+        &self,
+        container: &ContainerModel,
+        status: &str,
+        docker_id: Option<String>,
+        error_message: Option<String>,
+        details: StatusDetails,
+    ) -> Result<Option<ContainerModel>> {
+        // Only Failed containers carry a reason, any other transition clears it
+        let reason = match status {
+            "Failed" => error_message,
+            _ => None,
+        };
+        let now = Utc::now();
+
+        let status_changed_at = match container.status == status {
+            true => container.status_changed_at,
+            false => Some(now),
+        };
+
+        let mut update = ContainerEntity::update_many()
+            .col_expr(ContainerColumn::Status, Expr::value(status))
+            .col_expr(
+                ContainerColumn::StatusChangedAt,
+                Expr::value(status_changed_at),
+            )
+            .col_expr(ContainerColumn::ErrorMessage, Expr::value(reason.clone()))
+            .col_expr(ContainerColumn::UpdatedAt, Expr::value(now))
+            .col_expr(ContainerColumn::Version, Expr::value(container.version + 1));
+        if let Some(docker_id) = &docker_id {
+            update = update.col_expr(ContainerColumn::DockerId, Expr::value(docker_id.clone()));
+        }
+        if let Some(attempts) = details.attempts {
+            update = update.col_expr(
+                ContainerColumn::DockerAttempts,
+                Expr::value(i64::from(attempts)),
+            );
+        }
+        if let Some(exit_code) = details.exit_code {
+            update = update.col_expr(ContainerColumn::ExitCode, Expr::value(exit_code));
+        }
+        if let Some(image_digest) = &details.image_digest {
+            update = update.col_expr(
+                ContainerColumn::ImageDigest,
+                Expr::value(image_digest.clone()),
+            );
+        }
+        if let Some(docker_created_at) = details.docker_created_at {
+            update = update.col_expr(
+                ContainerColumn::DockerCreatedAt,
+                Expr::value(docker_created_at),
+            );
+        }
+        if let Some(spec_hash) = &details.spec_hash {
+            update = update.col_expr(ContainerColumn::SpecHash, Expr::value(spec_hash.clone()));
+        }
+        if let Some(restart_count) = details.restart_count {
+            update = update.col_expr(ContainerColumn::RestartCount, Expr::value(restart_count));
+        }
+        // Uptime runs from the start into Running or Paused until the container leaves them
+        let was_up = matches!(container.status.as_str(), "Running" | "Paused");
+        let is_up = matches!(status, "Running" | "Paused");
+        let (started_at, finished_at) = match (was_up, is_up) {
+            (false, true) => (Some(details.started_at.unwrap_or(now)), None),
+            (true, false) => (container.started_at, Some(now)),
+            _ => (container.started_at, container.finished_at),
+        };
+        update = update
+            .col_expr(ContainerColumn::StartedAt, Expr::value(started_at))
+            .col_expr(ContainerColumn::FinishedAt, Expr::value(finished_at));
+        // Health only means something while the container runs
+        let health = container.health.clone().filter(|_| is_up);
+        if health.is_none() {
+            update = update.col_expr(ContainerColumn::Health, Expr::value(Option::<String>::None));
+        }
+
+        // Writing first inside the transaction avoids SQLite lock upgrade failures
+        let txn = self.db.begin().await?;
+        let result = update
+            .filter(ContainerColumn::Id.eq(container.id.clone()))
+            .filter(ContainerColumn::Version.eq(container.version))
+            .exec(&txn)
+            .await?;
+
+        if result.rows_affected == 0 {
+            txn.rollback().await?;
+            warn!(
+                from_status = %container.status,
+                to_status = %status,
+                "Container changed concurrently, not updating its status"
+            );
+            // Still remember the Docker container so it can be cleaned up later. This can't
+            // be conditional on the version, the winner already moved it on. The version is
+            // still bumped so any writer holding a copy from before the id landed fails its
+            // conditional write and re-reads, instead of acting on a row without it.
+            if let Some(docker_id) = docker_id {
+                ContainerEntity::update_many()
+                    .col_expr(ContainerColumn::DockerId, Expr::value(docker_id))
+                    .col_expr(
+                        ContainerColumn::Version,
+                        Expr::col(ContainerColumn::Version).add(1),
+                    )
+                    .filter(ContainerColumn::Id.eq(container.id.clone()))
+                    .exec(&self.db)
+                    .await?;
+            }
+            return Ok(None);
+        }
+
+        audit_log::record_transition(
+            &txn,
+            &container.id,
+            Some(&container.status),
+            status,
+            reason.clone(),
+        )
+        .await?;
+        txn.commit().await?;
+
+        if status == "Failed" {
+            crate::metrics::record_container_failed();
+        }
+
+        info!(
+            from_status = %container.status,
+            to_status = %status,
+            "Updated container status"
+        );
+        let updated = ContainerModel {
+            status: status.to_string(),
+            docker_id: docker_id.or_else(|| container.docker_id.clone()),
+            error_message: reason,
+            version: container.version + 1,
+            docker_attempts: details
+                .attempts
+                .map_or(container.docker_attempts, i64::from),
+            exit_code: details.exit_code.unwrap_or(container.exit_code),
+            image_digest: details
+                .image_digest
+                .or_else(|| container.image_digest.clone()),
+            docker_created_at: details.docker_created_at.or(container.docker_created_at),
+            spec_hash: details.spec_hash.or_else(|| container.spec_hash.clone()),
+            restart_count: details.restart_count.unwrap_or(container.restart_count),
+            started_at,
+            finished_at,
+            health,
+            status_changed_at,
+            updated_at: now,
+            ..container.clone()
+        };
+        self.publish_status(&updated);
+        Ok(Some(updated))
+    }
+
+    fn publish_status(&self, container: &ContainerModel) {
+        // Only fails when nobody is watching
+        let _ = self
+            .status_updates
+            .send(ContainerStatusUpdate::from(container));
+    }
+
+    // Asks the loops to stop, start() returns once the in-flight reconciliation has finished
     pub fn shutdown(&self) {
-        if let Ok(mut signal) = self.shutdown_signal.lock() {
-            *signal = true;
+        self.shutdown.cancel();
+    }
+
+    pub async fn shutdown_requested(&self) {
+        self.shutdown.cancelled().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use futures::stream::BoxStream;
+    use sea_orm::{ConnectionTrait, DatabaseConnection, QueryOrder};
+    use std::collections::{HashMap, VecDeque};
+    use std::sync::Arc;
+
+    use crate::db::run_migrations;
+    use crate::services::docker::{ContainerState, ExistingContainer};
+
+    // In-memory stand-in for the Docker daemon. Clones share their state, so a test keeps
+    // one to inspect and script it while the processor owns the other.
+    #[derive(Clone, Default)]
+    struct FakeDocker {
+        state: Arc<Mutex<FakeState>>,
+    }
+
+    #[derive(Default)]
+    struct FakeState {
+        containers: HashMap<String, ExistingContainer>,
+        // Handed out by the next create_container and start_container calls, oldest first
+        create_errors: VecDeque<DockerError>,
+        start_errors: VecDeque<DockerError>,
+        // Ids passed to stop_container and remove_container, whether Docker knew them or not
+        stops: Vec<String>,
+        removals: Vec<String>,
+        // How long create_container takes, and how often it was entered so far
+        create_delay: Duration,
+        create_calls: usize,
+        // Images missing locally whose pull never completes
+        stalled_pulls: HashSet<String>,
+    }
+
+    impl FakeDocker {
+        fn state(&self) -> std::sync::MutexGuard<'_, FakeState> {
+            self.state.lock().unwrap()
+        }
+
+        fn container(&self, docker_id: &str) -> Option<ExistingContainer> {
+            self.state().containers.get(docker_id).cloned()
+        }
+
+        // The container stopping by itself, like a process exiting inside it
+        fn exit(&self, docker_id: &str, exit_code: i64) {
+            let mut state = self.state();
+            let container = state.containers.get_mut(docker_id).unwrap();
+            container.state.status = "exited".to_string();
+            container.state.exit_code = Some(exit_code);
+        }
+
+        // Removed behind the processor's back, e.g. with docker rm
+        fn forget(&self, docker_id: &str) {
+            self.state().containers.remove(docker_id);
+        }
+
+        // Fake ids are "fake-" plus the name, so either one finds the container
+        fn with_container<T>(
+            &self,
+            id_or_name: &str,
+            update: impl FnOnce(&mut ExistingContainer) -> T,
+        ) -> Result<T> {
+            let mut state = self.state();
+            let container = state
+                .containers
+                .values_mut()
+                .find(|container| {
+                    container.id == id_or_name || container.id == format!("fake-{}", id_or_name)
+                })
+                .ok_or_else(|| DockerError::ContainerNotFound(id_or_name.to_string()))?;
+            Ok(update(container))
+        }
+    }
+
+    #[async_trait]
+    impl DockerApi for FakeDocker {
+        async fn version(&self) -> Result<String> {
+            Ok("fake".to_string())
+        }
+
+        async fn image_exists(&self, image: &str) -> Result<bool> {
+            Ok(!self.state().stalled_pulls.contains(image))
         }
+
+        async fn image_digest(&self, _image: &str) -> Result<Option<String>> {
+            Ok(None)
+        }
+
+        async fn create_container(&self, request: &CreateContainerRequest) -> Result<String> {
+            let delay = {
+                let mut state = self.state();
+                state.create_calls += 1;
+                state.create_delay
+            };
+            tokio::time::sleep(delay).await;
+            // Like DockerService, missing images are pulled first
+            if !self.image_exists(&request.image).await? {
+                self.pull_image(&request.image).await?;
+            }
+
+            let mut state = self.state();
+            if let Some(e) = state.create_errors.pop_front() {
+                return Err(e.into());
+            }
+            let id = format!("fake-{}", request.name);
+            if state.containers.contains_key(&id) {
+                return Err(DockerError::NameConflict(request.name.clone()).into());
+            }
+            let container = ExistingContainer {
+                id: id.clone(),
+                image: Some(request.image.clone()),
+                created_at: Some(Utc::now()),
+                state: ContainerState {
+                    status: "created".to_string(),
+                    exit_code: None,
+                    started_at: None,
+                    health: None,
+                },
+            };
+            state.containers.insert(id.clone(), container);
+            Ok(id)
+        }
+
+        async fn start_container(&self, container_name: &str) -> Result<()> {
+            if let Some(e) = self.state().start_errors.pop_front() {
+                return Err(e.into());
+            }
+            self.with_container(container_name, |container| {
+                container.state.status = "running".to_string();
+                container.state.exit_code = None;
+                container.state.started_at = Some(Utc::now());
+            })
+        }
+
+        async fn stop_container(&self, container_name: &str, _timeout_secs: u64) -> Result<()> {
+            self.state().stops.push(container_name.to_string());
+            self.with_container(container_name, |container| {
+                if container.state.status == "running" {
+                    container.state.status = "exited".to_string();
+                    container.state.exit_code = Some(0);
+                }
+            })
+        }
+
+        async fn remove_container(&self, container_name: &str) -> Result<()> {
+            self.state().removals.push(container_name.to_string());
+            let id = self.with_container(container_name, |container| container.id.clone())?;
+            self.state().containers.remove(&id);
+            Ok(())
+        }
+
+        async fn get_container_state(&self, container_id: &str) -> Result<ContainerState> {
+            self.with_container(container_id, |container| container.state.clone())
+        }
+
+        async fn find_container(&self, id_or_name: &str) -> Result<Option<ExistingContainer>> {
+            Ok(self
+                .with_container(id_or_name, |container| container.clone())
+                .ok())
+        }
+
+        async fn fetch_image(&self, image: &str) -> Result<()> {
+            if self.state().stalled_pulls.contains(image) {
+                futures::future::pending::<()>().await;
+            }
+            Ok(())
+        }
+
+        fn pull_timeout(&self) -> Duration {
+            Duration::from_millis(50)
+        }
+
+        fn container_events(&self) -> BoxStream<'static, Result<String>> {
+            futures::stream::pending().boxed()
+        }
+
+        fn follow_container_logs_since(
+            &self,
+            _container_id: &str,
+            _since: i64,
+        ) -> BoxStream<'static, Result<String>> {
+            futures::stream::empty().boxed()
+        }
+    }
+
+    async fn setup(docker: &FakeDocker) -> (ProcessorService, DatabaseConnection) {
+        let config = Config::for_tests();
+        let db = crate::db::establish_connection(&config).await.unwrap();
+        run_migrations(&db).await.unwrap();
+        let processor = ProcessorService::new(&config, db.clone(), docker.clone())
+            .await
+            .unwrap();
+        (processor, db)
+    }
+
+    async fn insert_container(db: &DatabaseConnection, request: serde_json::Value) -> String {
+        let request: CreateContainerRequest = serde_json::from_value(request).unwrap();
+        let container = ContainerModel::from(request);
+        ContainerEntity::insert(container.clone().into_active_model())
+            .exec(db)
+            .await
+            .unwrap();
+        container.id
+    }
+
+    async fn load(db: &DatabaseConnection, id: &str) -> ContainerModel {
+        ContainerEntity::find_by_id(id.to_string())
+            .one(db)
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    // Same as the API's delete, the processor picks the Removing status up from there
+    async fn request_removal(db: &DatabaseConnection, id: &str) {
+        request_status(db, id, "Removing").await;
+    }
+
+    async fn request_status(db: &DatabaseConnection, id: &str, status: &str) {
+        let container = load(db, id).await;
+        ContainerEntity::update_many()
+            .col_expr(ContainerColumn::Status, Expr::value(status))
+            .col_expr(ContainerColumn::Version, Expr::value(container.version + 1))
+            .filter(ContainerColumn::Id.eq(id))
+            .exec(db)
+            .await
+            .unwrap();
+    }
+
+    // Pending needs two passes to run, one creating the Docker container and one starting it
+    async fn run_container(
+        processor: &ProcessorService,
+        db: &DatabaseConnection,
+        request: serde_json::Value,
+    ) -> ContainerModel {
+        let id = insert_container(db, request).await;
+        processor.reconcile_once().await.unwrap();
+        processor.reconcile_once().await.unwrap();
+        let container = load(db, &id).await;
+        assert_eq!(container.status, "Running");
+        container
+    }
+
+    #[tokio::test]
+    async fn pending_container_is_created_then_started() {
+        let docker = FakeDocker::default();
+        let (processor, db) = setup(&docker).await;
+        let id = insert_container(&db, serde_json::json!({"name": "web", "image": "nginx"})).await;
+
+        processor.reconcile_once().await.unwrap();
+        let container = load(&db, &id).await;
+        assert_eq!(container.status, "Created");
+        assert_eq!(container.docker_id.as_deref(), Some("fake-web"));
+        assert_eq!(container.spec_hash, Some(container.desired_spec_hash()));
+        assert_eq!(container.docker_attempts, 1);
+        assert_eq!(
+            docker.container("fake-web").unwrap().state.status,
+            "created"
+        );
+
+        processor.reconcile_once().await.unwrap();
+        let container = load(&db, &id).await;
+        assert_eq!(container.status, "Running");
+        assert!(container.started_at.is_some());
+        assert_eq!(
+            docker.container("fake-web").unwrap().state.status,
+            "running"
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_create_fails_the_container() {
+        let docker = FakeDocker::default();
+        docker
+            .state()
+            .create_errors
+            .push_back(DockerError::ImageNotFound("missing/app".to_string()));
+        let (processor, db) = setup(&docker).await;
+        let id = insert_container(
+            &db,
+            serde_json::json!({"name": "web", "image": "missing/app"}),
+        )
+        .await;
+
+        processor.reconcile_once().await.unwrap();
+        let container = load(&db, &id).await;
+        assert_eq!(container.status, "Failed");
+        assert_eq!(
+            container.error_message.as_deref(),
+            Some("image not found: missing/app")
+        );
+        assert!(container.docker_id.is_none());
+    }
+
+    #[tokio::test]
+    async fn transient_start_errors_are_retried() {
+        let docker = FakeDocker::default();
+        docker
+            .state()
+            .start_errors
+            .push_back(DockerError::Timeout("start".to_string()));
+        let (processor, db) = setup(&docker).await;
+
+        let container = run_container(
+            &processor,
+            &db,
+            serde_json::json!({"name": "web", "image": "nginx"}),
+        )
+        .await;
+        assert_eq!(container.docker_attempts, 2);
+    }
+
+    #[tokio::test]
+    async fn clean_exit_stops_the_container() {
+        let docker = FakeDocker::default();
+        let (processor, db) = setup(&docker).await;
+        let container = run_container(
+            &processor,
+            &db,
+            serde_json::json!({"name": "job", "image": "busybox"}),
+        )
+        .await;
+
+        docker.exit("fake-job", 0);
+        processor.reconcile_once().await.unwrap();
+        let container = load(&db, &container.id).await;
+        assert_eq!(container.status, "Stopped");
+        assert_eq!(container.exit_code, Some(0));
+        assert!(container.finished_at.is_some());
+        // Stopped containers are kept so they can be started again
+        assert!(docker.container("fake-job").is_some());
+    }
+
+    #[tokio::test]
+    async fn failing_exit_fails_the_container() {
+        let docker = FakeDocker::default();
+        let (processor, db) = setup(&docker).await;
+        let container = run_container(
+            &processor,
+            &db,
+            serde_json::json!({"name": "job", "image": "busybox"}),
+        )
+        .await;
+
+        docker.exit("fake-job", 3);
+        processor.reconcile_once().await.unwrap();
+        let container = load(&db, &container.id).await;
+        assert_eq!(container.status, "Failed");
+        assert_eq!(container.exit_code, Some(3));
+        assert_eq!(
+            container.error_message.as_deref(),
+            Some("Container exited with code 3")
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_container_is_cleaned_up_once() {
+        let docker = FakeDocker::default();
+        let (processor, db) = setup(&docker).await;
+        let container = run_container(
+            &processor,
+            &db,
+            serde_json::json!({"name": "job", "image": "busybox"}),
+        )
+        .await;
+
+        docker.exit("fake-job", 3);
+        processor.reconcile_once().await.unwrap();
+        let failed = load(&db, &container.id).await;
+        assert_eq!(failed.status, "Failed");
+        assert!(failed.docker_id.is_none());
+        assert!(docker.container("fake-job").is_none());
+
+        // Later passes have nothing left to remove
+        processor.reconcile_once().await.unwrap();
+        assert_eq!(docker.state().removals, vec!["fake-job".to_string()]);
+        let container = load(&db, &container.id).await;
+        assert_eq!(container.status, "Failed");
+        assert_eq!(container.error_message, failed.error_message);
+    }
+
+    #[tokio::test]
+    async fn stopped_container_is_stopped_once_and_kept() {
+        let docker = FakeDocker::default();
+        let (processor, db) = setup(&docker).await;
+        let container = run_container(
+            &processor,
+            &db,
+            serde_json::json!({"name": "web", "image": "nginx"}),
+        )
+        .await;
+
+        // Same as the API's stop action. Reconciled on its own, reconcile_once would first
+        // take the still running Docker container as the truth and correct the row back
+        request_status(&db, &container.id, "Stopped").await;
+        processor.reconcile_container(&container.id).await.unwrap();
+        assert_eq!(docker.state().stops, vec!["fake-web".to_string()]);
+        assert_eq!(docker.container("fake-web").unwrap().state.status, "exited");
+        let stopped = load(&db, &container.id).await;
+        assert_eq!(stopped.status, "Stopped");
+        assert_eq!(stopped.docker_id.as_deref(), Some("fake-web"));
+
+        // Once Docker agrees there is nothing left to do, the container stays for a restart
+        processor.reconcile_container(&container.id).await.unwrap();
+        processor.reconcile_once().await.unwrap();
+        assert_eq!(docker.state().stops.len(), 1);
+        assert!(docker.state().removals.is_empty());
+        let container = load(&db, &container.id).await;
+        assert_eq!(container.docker_id.as_deref(), Some("fake-web"));
+        assert_eq!(container.version, stopped.version);
+    }
+
+    #[tokio::test]
+    async fn on_failure_policy_restarts_the_container() {
+        let docker = FakeDocker::default();
+        let (processor, db) = setup(&docker).await;
+        let container = run_container(
+            &processor,
+            &db,
+            serde_json::json!({"name": "job", "image": "busybox", "restart_policy": "on-failure"}),
+        )
+        .await;
+
+        docker.exit("fake-job", 1);
+        processor.reconcile_once().await.unwrap();
+        let restarted = load(&db, &container.id).await;
+        assert_eq!(restarted.status, "Running");
+        assert_eq!(restarted.restart_count, 1);
+        assert_eq!(restarted.exit_code, Some(1));
+        let docker_state = docker.container("fake-job").unwrap().state;
+        assert_eq!(docker_state.status, "running");
+
+        // The new run gets Docker's start time and its own transitions
+        assert_eq!(restarted.started_at, docker_state.started_at);
+        assert!(restarted.started_at > container.started_at);
+        assert!(restarted.status_changed_at > container.status_changed_at);
+        let transitions: Vec<(Option<String>, String)> = audit_log::Entity::find()
+            .filter(audit_log::Column::ContainerId.eq(container.id.clone()))
+            .order_by_asc(audit_log::Column::Timestamp)
+            .all(&db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.from_status, entry.to_status))
+            .collect();
+        assert_eq!(
+            transitions[transitions.len() - 2..],
+            [
+                (Some("Running".to_string()), "Restarting".to_string()),
+                (Some("Restarting".to_string()), "Running".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn vanished_container_is_failed() {
+        let docker = FakeDocker::default();
+        let (processor, db) = setup(&docker).await;
+        let container = run_container(
+            &processor,
+            &db,
+            serde_json::json!({"name": "web", "image": "nginx"}),
+        )
+        .await;
+
+        docker.forget("fake-web");
+        processor.reconcile_once().await.unwrap();
+        let container = load(&db, &container.id).await;
+        assert_eq!(container.status, "Failed");
+        assert!(container.error_message.is_some());
+    }
+
+    #[tokio::test]
+    async fn auto_remove_container_is_removed_after_exit() {
+        let docker = FakeDocker::default();
+        let (processor, db) = setup(&docker).await;
+        let container = run_container(
+            &processor,
+            &db,
+            serde_json::json!({"name": "job", "image": "busybox", "auto_remove": true}),
+        )
+        .await;
+
+        // Docker deletes auto_remove containers as soon as they exit
+        docker.forget("fake-job");
+        processor.reconcile_once().await.unwrap();
+        let container = load(&db, &container.id).await;
+        assert_eq!(container.status, "Removed");
+        assert!(container.deleted_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn removing_container_is_cleaned_up() {
+        let docker = FakeDocker::default();
+        let (processor, db) = setup(&docker).await;
+        let container = run_container(
+            &processor,
+            &db,
+            serde_json::json!({"name": "web", "image": "nginx"}),
+        )
+        .await;
+
+        request_removal(&db, &container.id).await;
+        processor.reconcile_once().await.unwrap();
+        let container = load(&db, &container.id).await;
+        assert_eq!(container.status, "Removed");
+        assert!(container.deleted_at.is_some());
+        assert!(container.owner.is_none());
+        assert!(docker.container("fake-web").is_none());
+    }
+
+    #[tokio::test]
+    async fn removing_container_without_docker_id_is_deleted() {
+        let docker = FakeDocker::default();
+        let (processor, db) = setup(&docker).await;
+        let id = insert_container(&db, serde_json::json!({"name": "web", "image": "nginx"})).await;
+
+        // Deleted before the processor ever created it in Docker
+        request_removal(&db, &id).await;
+        processor.reconcile_once().await.unwrap();
+        let container = load(&db, &id).await;
+        assert_eq!(container.status, "Removed");
+        assert!(container.deleted_at.is_some());
+        assert!(container.docker_id.is_none());
+        assert!(docker.state().removals.is_empty());
+        assert!(docker.state().containers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn changed_spec_recreates_the_container() {
+        let docker = FakeDocker::default();
+        let (processor, db) = setup(&docker).await;
+        let container = run_container(
+            &processor,
+            &db,
+            serde_json::json!({"name": "web", "image": "nginx"}),
+        )
+        .await;
+
+        ContainerEntity::update_many()
+            .col_expr(ContainerColumn::Image, Expr::value("nginx:1.27"))
+            .col_expr(ContainerColumn::Version, Expr::value(container.version + 1))
+            .filter(ContainerColumn::Id.eq(container.id.clone()))
+            .exec(&db)
+            .await
+            .unwrap();
+        processor.reconcile_once().await.unwrap();
+        assert_eq!(load(&db, &container.id).await.status, "Pending");
+
+        processor.reconcile_once().await.unwrap();
+        let container = load(&db, &container.id).await;
+        assert_eq!(container.status, "Created");
+        assert_eq!(container.spec_hash, Some(container.desired_spec_hash()));
+        let recreated = docker.container("fake-web").unwrap();
+        assert_eq!(recreated.image.as_deref(), Some("nginx:1.27"));
+        assert_eq!(recreated.state.status, "created");
+    }
+
+    #[tokio::test]
+    async fn shutdown_waits_for_in_flight_reconciliation() {
+        let docker = FakeDocker::default();
+        docker.state().create_delay = Duration::from_millis(300);
+        let (processor, db) = setup(&docker).await;
+        let id = insert_container(&db, serde_json::json!({"name": "web", "image": "nginx"})).await;
+
+        let processor = Arc::new(processor);
+        let running = tokio::spawn({
+            let processor = processor.clone();
+            async move { processor.start().await }
+        });
+        while docker.state().create_calls == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // Cancelled while Docker is still creating the container
+        processor.shutdown();
+        tokio::time::timeout(Duration::from_secs(5), running)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+
+        let container = load(&db, &id).await;
+        assert_eq!(container.status, "Created");
+        assert_eq!(container.docker_id.as_deref(), Some("fake-web"));
+        assert!(docker.container("fake-web").is_some());
+        assert_eq!(docker.state().create_calls, 1);
+    }
+
+    #[tokio::test]
+    async fn racing_status_writes_keep_the_first() {
+        let docker = FakeDocker::default();
+        let (processor, db) = setup(&docker).await;
+        let id = insert_container(&db, serde_json::json!({"name": "web", "image": "nginx"})).await;
+        let first = load(&db, &id).await;
+        let second = load(&db, &id).await;
+
+        let written = processor
+            .write_container_status(&first, "Removing", None, None, StatusDetails::default())
+            .await
+            .unwrap();
+        assert!(written.is_some());
+
+        // Same version as the first writer read, so this one loses
+        let written = processor
+            .write_container_status(
+                &second,
+                "Failed",
+                Some("fake-web".to_string()),
+                Some("create failed".to_string()),
+                StatusDetails::default(),
+            )
+            .await
+            .unwrap();
+        assert!(written.is_none());
+
+        let container = load(&db, &id).await;
+        assert_eq!(container.status, "Removing");
+        assert!(container.error_message.is_none());
+        // The loser's Docker container is remembered so it gets cleaned up
+        assert_eq!(container.docker_id.as_deref(), Some("fake-web"));
+        assert_eq!(container.version, first.version + 2);
+    }
+
+    #[tokio::test]
+    async fn stalled_pull_times_out() {
+        let docker = FakeDocker::default();
+        docker.state().stalled_pulls.insert("slow/app".to_string());
+
+        let error = docker.pull_image("slow/app").await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<DockerError>(),
+            Some(DockerError::Timeout(_))
+        ));
+
+        // Timeouts count as transient, the create gives up once its retries are used
+        let (processor, db) = setup(&docker).await;
+        let id =
+            insert_container(&db, serde_json::json!({"name": "web", "image": "slow/app"})).await;
+        processor.reconcile_once().await.unwrap();
+        let container = load(&db, &id).await;
+        assert_eq!(container.status, "Failed");
+        assert_eq!(container.docker_attempts, 3);
+        assert!(container
+            .error_message
+            .unwrap()
+            .starts_with("Docker request timed out: pulling image slow/app"));
+        assert!(container.docker_id.is_none());
+    }
+
+    #[tokio::test]
+    async fn failed_start_write_stops_the_container_again() {
+        let docker = FakeDocker::default();
+        let (processor, db) = setup(&docker).await;
+        let id = insert_container(&db, serde_json::json!({"name": "web", "image": "nginx"})).await;
+        processor.reconcile_once().await.unwrap();
+        let created = load(&db, &id).await;
+        assert_eq!(created.status, "Created");
+
+        // Fails the audit row, the last write in the status transaction
+        db.execute_unprepared(
+            "CREATE TRIGGER fail_running BEFORE INSERT ON audit_log \
+             WHEN NEW.to_status = 'Running' BEGIN SELECT RAISE(ABORT, 'injected'); END",
+        )
+        .await
+        .unwrap();
+        // reconcile_once reports per-container errors only in the log
+        processor.reconcile_once().await.unwrap();
+        let container = load(&db, &id).await;
+        assert_eq!(container.status, "Created");
+        assert_eq!(container.version, created.version);
+        assert!(container.started_at.is_none());
+        let docker_state = docker.container("fake-web").unwrap().state;
+        assert_eq!(docker_state.status, "exited");
+
+        // The next pass starts it again once the database recovers
+        db.execute_unprepared("DROP TRIGGER fail_running")
+            .await
+            .unwrap();
+        processor.reconcile_once().await.unwrap();
+        assert_eq!(load(&db, &id).await.status, "Running");
+        assert_eq!(
+            docker.container("fake-web").unwrap().state.status,
+            "running"
+        );
     }
 }
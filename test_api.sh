@@ -130,5 +130,119 @@ else
     echo "❌ Get non-existent container failed (expected 404, got $not_found_status)"
 fi
 
+# Test create container with entrypoint and command overrides
+echo -e "\n9️⃣  Testing create container with an entrypoint and a command..."
+command_data='{"name": "test-busybox", "image": "busybox:latest", "entrypoint": ["/bin/sh", "-c"], "command": ["sleep 3600"]}'
+command_result=$(make_request "POST" "$BASE_URL/containers" "$command_data")
+command_response=$(echo "$command_result" | sed '$d')
+command_status=$(echo "$command_result" | tail -n1)
+echo "$command_response" | jq .
+
+if check_error "$command_response" "$command_status" "Create container with command"; then
+    if [ "$(echo "$command_response" | jq -c '[.entrypoint, .command]')" = '[["/bin/sh","-c"],["sleep 3600"]]' ]; then
+        echo "✅ Entrypoint and command round-trip passed"
+    else
+        echo "❌ Entrypoint and command round-trip failed (got $(echo "$command_response" | jq -c '[.entrypoint, .command]'))"
+    fi
+    make_request "DELETE" "$BASE_URL/containers/$(echo "$command_response" | jq -r '.id')" > /dev/null
+fi
+
+empty_status=$(make_request "POST" "$BASE_URL/containers" '{"name": "test-empty-entrypoint", "image": "busybox:latest", "entrypoint": [], "command": []}' | tail -n1)
+if [ "$empty_status" -eq 422 ]; then
+    echo "✅ Empty entrypoint and command rejected"
+else
+    echo "❌ Empty entrypoint and command not rejected (expected 422, got $empty_status)"
+fi
+
+# Test create container with an overlong name (should return 422 and store nothing)
+echo -e "\n🔟 Testing create container with a 300 character name (should return 422)..."
+long_name=$(printf 'a%.0s' $(seq 1 300))
+long_result=$(make_request "POST" "$BASE_URL/containers" "{\"name\": \"$long_name\", \"image\": \"nginx:latest\"}")
+long_response=$(echo "$long_result" | sed '$d')
+long_status=$(echo "$long_result" | tail -n1)
+echo "$long_response" | jq .
+
+long_count=$(make_request "GET" "$BASE_URL/containers" | sed '$d' | jq --arg name "$long_name" '[.items[] | select(.name == $name)] | length')
+if [ "$long_status" -eq 422 ] && [ "$long_count" -eq 0 ]; then
+    echo "✅ Overlong name rejected before anything was stored"
+else
+    echo "❌ Overlong name check failed (status $long_status, $long_count stored)"
+fi
+
+# Test filtering containers by image (exact and prefix match)
+echo -e "\n1️⃣1️⃣  Testing list containers filtered by image..."
+nginx_id=$(make_request "POST" "$BASE_URL/containers" '{"name": "test-image-nginx", "image": "nginx:latest"}' | sed '$d' | jq -r '.id')
+busybox_id=$(make_request "POST" "$BASE_URL/containers" '{"name": "test-image-busybox", "image": "busybox:latest"}' | sed '$d' | jq -r '.id')
+
+# Other tests may leave containers behind, so only check that each filter returns its
+# own container and nothing with another image
+nginx_ok=$(make_request "GET" "$BASE_URL/containers?image=nginx:latest" | sed '$d' | jq --arg id "$nginx_id" 'all(.items[]; .image == "nginx:latest") and any(.items[]; .id == $id)')
+busybox_ok=$(make_request "GET" "$BASE_URL/containers?image=busybox*" | sed '$d' | jq --arg id "$busybox_id" 'all(.items[]; .image | startswith("busybox")) and any(.items[]; .id == $id)')
+if [ "$nginx_ok" = "true" ] && [ "$busybox_ok" = "true" ]; then
+    echo "✅ Image filter passed"
+else
+    echo "❌ Image filter failed (exact match: $nginx_ok, prefix match: $busybox_ok)"
+fi
+make_request "DELETE" "$BASE_URL/containers/$nginx_id" > /dev/null
+make_request "DELETE" "$BASE_URL/containers/$busybox_id" > /dev/null
+
+# Test auto_remove: rejected with a restart policy, otherwise Removed once the container exits
+echo -e "\n1️⃣2️⃣  Testing create container with auto_remove..."
+conflict_status=$(make_request "POST" "$BASE_URL/containers" '{"name": "test-auto-remove-always", "image": "busybox:latest", "auto_remove": true, "restart_policy": "always"}' | tail -n1)
+auto_id=$(make_request "POST" "$BASE_URL/containers" '{"name": "test-auto-remove", "image": "busybox:latest", "command": ["true"], "auto_remove": true}' | sed '$d' | jq -r '.id')
+
+auto_status=""
+for _ in $(seq 1 30); do
+    auto_status=$(make_request "GET" "$BASE_URL/containers/$auto_id?include_deleted=true" | sed '$d' | jq -r '.status')
+    [ "$auto_status" = "Removed" ] && break
+    sleep 1
+done
+if [ "$conflict_status" -eq 400 ] && [ "$auto_status" = "Removed" ]; then
+    echo "✅ Auto remove passed"
+else
+    echo "❌ Auto remove failed (restart policy conflict: $conflict_status, status after exit: $auto_status)"
+fi
+
+# Test that changing env through PATCH recreates the running container
+echo -e "\n1️⃣3️⃣  Testing env update recreates the container..."
+env_id=$(make_request "POST" "$BASE_URL/containers" '{"name": "test-env-update", "image": "nginx:latest", "env": ["MODE=a"]}' | sed '$d' | jq -r '.id')
+
+wait_for_running() {
+    for _ in $(seq 1 30); do
+        container=$(make_request "GET" "$BASE_URL/containers/$1" | sed '$d')
+        if [ "$(echo "$container" | jq -r '.status')" = "Running" ] && [ "$(echo "$container" | jq -r '.docker_created_at')" != "$2" ]; then
+            echo "$container"
+            return
+        fi
+        sleep 1
+    done
+}
+
+first_created=$(wait_for_running "$env_id" "" | jq -r '.docker_created_at')
+patch_status=$(make_request "PATCH" "$BASE_URL/containers/$env_id" '{"env": ["MODE=b"]}' | tail -n1)
+recreated=$(wait_for_running "$env_id" "$first_created")
+if [ "$patch_status" -eq 200 ] && [ "$(echo "$recreated" | jq -c '.env')" = '["MODE=b"]' ]; then
+    echo "✅ Env update recreated the container"
+else
+    echo "❌ Env update failed (PATCH status $patch_status, container after update: $(echo "$recreated" | jq -c '{status, env, docker_created_at}'))"
+fi
+make_request "DELETE" "$BASE_URL/containers/$env_id" > /dev/null
+
+# Test that renaming through PATCH renames the Docker container too, and rejects taken names
+echo -e "\n1️⃣4️⃣  Testing container rename..."
+rename_id=$(make_request "POST" "$BASE_URL/containers" '{"name": "test-rename-old", "image": "nginx:latest"}' | sed '$d' | jq -r '.id')
+taken_id=$(make_request "POST" "$BASE_URL/containers" '{"name": "test-rename-taken", "image": "nginx:latest"}' | sed '$d' | jq -r '.id')
+wait_for_running "$rename_id" "" > /dev/null
+rename_status=$(make_request "PATCH" "$BASE_URL/containers/$rename_id" '{"name": "test-rename-new"}' | tail -n1)
+docker_name=$(make_request "GET" "$BASE_URL/containers/$rename_id/inspect" | sed '$d' | jq -r '.Name')
+taken_status=$(make_request "PATCH" "$BASE_URL/containers/$rename_id" '{"name": "test-rename-taken"}' | tail -n1)
+if [ "$rename_status" -eq 200 ] && [ "$docker_name" = "/test-rename-new" ] && [ "$taken_status" -eq 409 ]; then
+    echo "✅ Container rename passed"
+else
+    echo "❌ Container rename failed (PATCH status $rename_status, Docker name $docker_name, taken name status $taken_status)"
+fi
+make_request "DELETE" "$BASE_URL/containers/$rename_id" > /dev/null
+make_request "DELETE" "$BASE_URL/containers/$taken_id" > /dev/null
+
 echo -e "\n🎉 API test completed!"
 echo "==================================" 
\ No newline at end of file